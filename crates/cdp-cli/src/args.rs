@@ -0,0 +1,182 @@
+use anchor_lang::prelude::Pubkey;
+use cdp_stablecoin_protocol::state::{
+    AuctionParams, LiquidationMode, OracleConfig, OracleSource, PauseFlags, PriceMode,
+    SecondaryOracle,
+};
+use clap::{Args, ValueEnum};
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SourceArg {
+    Pyth,
+    Switchboard,
+    Mock,
+}
+
+impl From<SourceArg> for OracleSource {
+    fn from(source: SourceArg) -> Self {
+        match source {
+            SourceArg::Pyth => OracleSource::Pyth,
+            SourceArg::Switchboard => OracleSource::Switchboard,
+            SourceArg::Mock => OracleSource::Mock,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum PriceModeArg {
+    Spot,
+    Ema,
+    Conservative,
+}
+
+impl From<PriceModeArg> for PriceMode {
+    fn from(mode: PriceModeArg) -> Self {
+        match mode {
+            PriceModeArg::Spot => PriceMode::Spot,
+            PriceModeArg::Ema => PriceMode::Ema,
+            PriceModeArg::Conservative => PriceMode::Conservative,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum LiquidationModeArg {
+    StabilityPool,
+    Auction,
+}
+
+impl From<LiquidationModeArg> for LiquidationMode {
+    fn from(mode: LiquidationModeArg) -> Self {
+        match mode {
+            LiquidationModeArg::StabilityPool => LiquidationMode::StabilityPool,
+            LiquidationModeArg::Auction => LiquidationMode::Auction,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum PauseActionArg {
+    Open,
+    Repay,
+    Liquidate,
+    Redeem,
+    Stake,
+    Unstake,
+    Claim,
+    InterestUpdate,
+}
+
+// Flags with exactly the listed actions paused
+pub fn pause_flags(actions: &[PauseActionArg]) -> PauseFlags {
+    let paused = |action| actions.contains(&action);
+
+    PauseFlags {
+        open: paused(PauseActionArg::Open),
+        repay: paused(PauseActionArg::Repay),
+        liquidate: paused(PauseActionArg::Liquidate),
+        redeem: paused(PauseActionArg::Redeem),
+        stake: paused(PauseActionArg::Stake),
+        unstake: paused(PauseActionArg::Unstake),
+        claim: paused(PauseActionArg::Claim),
+        interest_update: paused(PauseActionArg::InterestUpdate),
+    }
+}
+
+#[derive(Args)]
+pub struct OracleArgs {
+    /// Oracle backend of the feed
+    #[arg(long, value_enum, default_value = "pyth")]
+    pub source: SourceArg,
+    /// Which of spot and EMA prices are read
+    #[arg(long, value_enum, default_value = "conservative")]
+    pub price_mode: PriceModeArg,
+    /// Oldest accepted price, in seconds
+    #[arg(long, default_value_t = 60)]
+    pub max_price_age: u64,
+    /// Widest accepted confidence interval, in bps of the price
+    #[arg(long, default_value_t = 200)]
+    pub max_confidence: u16,
+}
+
+impl From<&OracleArgs> for OracleConfig {
+    fn from(args: &OracleArgs) -> Self {
+        OracleConfig {
+            source: args.source.into(),
+            price_mode: args.price_mode.into(),
+            max_price_age: args.max_price_age,
+            max_confidence: args.max_confidence,
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct SecondaryOracleArgs {
+    /// Hex feed id of a secondary oracle to cross-check prices against
+    #[arg(long)]
+    pub secondary_feed: Option<String>,
+    #[arg(long, value_enum, default_value = "switchboard")]
+    pub secondary_source: SourceArg,
+    #[arg(long, value_enum, default_value = "spot")]
+    pub secondary_price_mode: PriceModeArg,
+    #[arg(long, default_value_t = 60)]
+    pub secondary_max_price_age: u64,
+    #[arg(long, default_value_t = 200)]
+    pub secondary_max_confidence: u16,
+    /// Largest accepted gap between the two prices, in bps
+    #[arg(long, default_value_t = 200)]
+    pub max_deviation: u16,
+}
+
+impl SecondaryOracleArgs {
+    pub fn secondary_oracle(&self) -> anyhow::Result<Option<SecondaryOracle>> {
+        let Some(feed) = &self.secondary_feed else {
+            return Ok(None);
+        };
+
+        Ok(Some(SecondaryOracle {
+            price_feed: cdp_stablecoin_protocol::oracle::parse_feed_id(feed)?,
+            oracle: OracleConfig {
+                source: self.secondary_source.into(),
+                price_mode: self.secondary_price_mode.into(),
+                max_price_age: self.secondary_max_price_age,
+                max_confidence: self.secondary_max_confidence,
+            },
+            max_deviation: self.max_deviation,
+        }))
+    }
+}
+
+#[derive(Args)]
+pub struct AuctionArgs {
+    /// Premium over the oracle price an auction starts at, in bps
+    #[arg(long, default_value_t = 1000)]
+    pub auction_start_premium: u16,
+    /// Lowest auction price, in bps of the oracle price
+    #[arg(long, default_value_t = 8000)]
+    pub auction_floor: u16,
+    /// Seconds for the price to decay from start to floor
+    #[arg(long, default_value_t = 3600)]
+    pub auction_duration: i64,
+}
+
+impl From<&AuctionArgs> for AuctionParams {
+    fn from(args: &AuctionArgs) -> Self {
+        AuctionParams {
+            start_premium: args.auction_start_premium,
+            floor: args.auction_floor,
+            duration: args.auction_duration,
+        }
+    }
+}
+
+// Price accounts to pass to collateral instructions, derived from the collateral config
+// unless given explicitly
+#[derive(Args)]
+pub struct PriceFeedArgs {
+    /// Price account of the collateral's primary feed
+    #[arg(long)]
+    pub price_feed: Option<Pubkey>,
+    /// Price account of the collateral's secondary feed
+    #[arg(long)]
+    pub secondary_price_feed: Option<Pubkey>,
+}
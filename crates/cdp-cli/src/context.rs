@@ -0,0 +1,178 @@
+use anchor_lang::{prelude::Pubkey, AccountDeserialize};
+use anyhow::{anyhow, Context as _};
+use cdp_client::{
+    accounts::{self, CollateralConfig, Position, ProtocolConfig},
+    oracle::price_feed_address,
+    pda, position_list, rpc,
+};
+use cdp_stablecoin_protocol::{
+    oracle::{parse_feed_id, OraclePrice},
+    state::{OracleConfig, SecondaryOracle},
+};
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig, instruction::Instruction, signature::Keypair,
+    signer::Signer,
+};
+
+use crate::args::PriceFeedArgs;
+
+pub struct Context {
+    pub rpc: RpcClient,
+    pub payer: Keypair,
+}
+
+impl Context {
+    pub fn new(url: &str, keypair_path: &str) -> anyhow::Result<Self> {
+        Ok(Context {
+            rpc: RpcClient::new_with_commitment(url.to_string(), CommitmentConfig::confirmed()),
+            payer: rpc::read_keypair(keypair_path)?,
+        })
+    }
+
+    pub fn pubkey(&self) -> Pubkey {
+        self.payer.pubkey()
+    }
+
+    pub fn fetch<T: AccountDeserialize>(&self, key: &Pubkey) -> anyhow::Result<T> {
+        let account = self
+            .rpc
+            .get_account(key)
+            .with_context(|| format!("cannot fetch account {}", key))?;
+
+        Ok(accounts::decode(&account.data)?)
+    }
+
+    pub fn send(&self, instruction: Instruction) -> anyhow::Result<()> {
+        let signature = rpc::send_instruction(&self.rpc, &self.payer, instruction)?;
+        println!("Signature: {}", signature);
+
+        Ok(())
+    }
+
+    pub fn collateral_config(&self, collateral_mint: &Pubkey) -> anyhow::Result<CollateralConfig> {
+        self.fetch(&pda::collateral_vault_config(collateral_mint))
+    }
+
+    // Primary and secondary price accounts of a collateral, explicit ones first
+    pub fn price_feeds(
+        &self,
+        collateral_config: &CollateralConfig,
+        args: &PriceFeedArgs,
+    ) -> anyhow::Result<(Pubkey, Option<Pubkey>)> {
+        let price_feed = args
+            .price_feed
+            .or_else(|| {
+                price_feed_address(
+                    &collateral_config.collateral_price_feed,
+                    &collateral_config.collateral_oracle,
+                )
+            })
+            .ok_or_else(|| anyhow!("pass --price-feed for a Pyth collateral"))?;
+
+        let secondary_price_feed = match collateral_config.secondary_oracle {
+            Some(secondary_oracle) => Some(
+                args.secondary_price_feed
+                    .or_else(|| {
+                        price_feed_address(&secondary_oracle.price_feed, &secondary_oracle.oracle)
+                    })
+                    .ok_or_else(|| anyhow!("pass --secondary-price-feed for a Pyth feed"))?,
+            ),
+            None => None,
+        };
+
+        Ok((price_feed, secondary_price_feed))
+    }
+
+    // Stablecoin price account, derived from the config unless given
+    pub fn stablecoin_price_feed(&self, price_feed: Option<Pubkey>) -> anyhow::Result<Pubkey> {
+        if let Some(price_feed) = price_feed {
+            return Ok(price_feed);
+        }
+
+        let protocol_config: ProtocolConfig = self.fetch(&pda::protocol_config())?;
+
+        price_feed_address(
+            &protocol_config.stablecoin_price_feed,
+            &protocol_config.stablecoin_oracle,
+        )
+        .ok_or_else(|| anyhow!("pass --price-feed for a Pyth stablecoin feed"))
+    }
+
+    // Stablecoin price account for a feed that is not stored yet, when initialising the
+    // config or replacing its feed
+    pub fn new_stablecoin_price_feed(
+        &self,
+        feed: &str,
+        stablecoin_oracle: &OracleConfig,
+        price_feed: Option<Pubkey>,
+    ) -> anyhow::Result<Pubkey> {
+        price_feed
+            .or_else(|| price_feed_address(&parse_feed_id(feed).ok()?, stablecoin_oracle))
+            .ok_or_else(|| anyhow!("pass --price-feed for a Pyth stablecoin feed"))
+    }
+
+    // Price accounts for oracle settings that are not stored yet, when listing a
+    // collateral or replacing its feeds
+    pub fn new_price_feeds(
+        &self,
+        feed: &str,
+        collateral_oracle: &OracleConfig,
+        secondary_oracle: Option<&SecondaryOracle>,
+        args: &PriceFeedArgs,
+    ) -> anyhow::Result<(Pubkey, Option<Pubkey>)> {
+        let price_feed = args
+            .price_feed
+            .or_else(|| price_feed_address(&parse_feed_id(feed).ok()?, collateral_oracle))
+            .ok_or_else(|| anyhow!("pass --price-feed for a Pyth collateral"))?;
+
+        let secondary_price_feed = match secondary_oracle {
+            Some(secondary_oracle) => Some(
+                args.secondary_price_feed
+                    .or_else(|| {
+                        price_feed_address(&secondary_oracle.price_feed, &secondary_oracle.oracle)
+                    })
+                    .ok_or_else(|| anyhow!("pass --secondary-price-feed for a Pyth feed"))?,
+            ),
+            None => None,
+        };
+
+        Ok((price_feed, secondary_price_feed))
+    }
+
+    // Primary collateral price as the program decodes it, without the staleness check
+    pub fn collateral_price(
+        &self,
+        collateral_config: &CollateralConfig,
+        price_feed: &Pubkey,
+    ) -> anyhow::Result<OraclePrice> {
+        let account = self.rpc.get_account(price_feed)?;
+
+        Ok(cdp_client::oracle::read_price(
+            price_feed,
+            &account.owner,
+            &account.data,
+            &collateral_config.collateral_price_feed,
+            &collateral_config.collateral_oracle,
+        )?)
+    }
+
+    // Positions around the user's position and around the place `moved`, its state after
+    // the instruction, takes in the collateral's position list
+    pub fn position_accounts(
+        &self,
+        collateral_mint: &Pubkey,
+        collateral_config: &CollateralConfig,
+        user: &Pubkey,
+        moved: Option<&Position>,
+    ) -> anyhow::Result<Vec<Pubkey>> {
+        let positions = rpc::collateral_positions(&self.rpc, collateral_mint)?;
+
+        Ok(position_list::move_accounts(
+            &positions,
+            collateral_config,
+            &pda::position(user, collateral_mint),
+            moved,
+        )?)
+    }
+}
@@ -0,0 +1,723 @@
+mod args;
+mod context;
+mod show;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token::Mint;
+use args::{
+    pause_flags, AuctionArgs, LiquidationModeArg, OracleArgs, PauseActionArg, PriceFeedArgs,
+    SecondaryOracleArgs,
+};
+use cdp_client::{
+    accounts::{Auction, Position, ProtocolConfig},
+    health, instructions, pda, position_list, rpc,
+};
+#[cfg(feature = "mock-oracle")]
+use cdp_stablecoin_protocol::oracle::parse_feed_id;
+use cdp_stablecoin_protocol::state::OracleConfig;
+use clap::{Parser, Subcommand};
+use context::Context;
+
+/// Operate the CDP stablecoin protocol from the command line
+#[derive(Parser)]
+struct Cli {
+    /// RPC endpoint of the cluster
+    #[arg(long, global = true, default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Keypair that signs and pays for transactions
+    #[arg(long, global = true, default_value = "~/.config/solana/id.json")]
+    keypair: String,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the protocol config, stablecoin mint and vaults
+    InitConfig {
+        #[arg(long)]
+        protocol_fee: u16,
+        #[arg(long)]
+        redemption_fee: u16,
+        #[arg(long)]
+        mint_fee: u16,
+        #[arg(long)]
+        base_rate: u16,
+        #[arg(long)]
+        sigma: u16,
+        #[arg(long)]
+        debt_ceiling: u64,
+        /// Hex feed id of the stablecoin price
+        #[arg(long)]
+        stablecoin_feed: String,
+        #[command(flatten)]
+        oracle: OracleArgs,
+        /// Price account of the stablecoin feed, derived from the feed id unless given
+        #[arg(long)]
+        price_feed: Option<Pubkey>,
+    },
+    /// Update protocol fees, rate parameters and the global debt ceiling
+    UpdateConfig {
+        #[arg(long)]
+        protocol_fee: u16,
+        #[arg(long)]
+        redemption_fee: u16,
+        #[arg(long)]
+        mint_fee: u16,
+        #[arg(long)]
+        base_rate: u16,
+        #[arg(long)]
+        sigma: u16,
+        #[arg(long)]
+        debt_ceiling: u64,
+        /// Price account of the stablecoin feed, derived from the config unless given
+        #[arg(long)]
+        price_feed: Option<Pubkey>,
+    },
+    /// List a new collateral type
+    ListCollateral {
+        #[arg(long)]
+        mint: Pubkey,
+        /// Hex feed id of the collateral price
+        #[arg(long)]
+        feed: String,
+        #[command(flatten)]
+        oracle: OracleArgs,
+        #[command(flatten)]
+        secondary_oracle: SecondaryOracleArgs,
+        #[command(flatten)]
+        price_feeds: PriceFeedArgs,
+        #[arg(long)]
+        max_ltv: u16,
+        #[arg(long)]
+        liquidation_threshold: u16,
+        #[arg(long)]
+        liquidation_penalty: u16,
+        #[arg(long)]
+        close_factor: u16,
+        #[arg(long)]
+        liquidator_reward: u16,
+        #[arg(long, value_enum, default_value = "stability-pool")]
+        liquidation_mode: LiquidationModeArg,
+        #[command(flatten)]
+        auction: AuctionArgs,
+        #[arg(long)]
+        debt_ceiling: u64,
+        #[arg(long)]
+        min_debt: u64,
+    },
+    /// Deposit collateral and mint stablecoins
+    OpenPosition {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        collateral_amount: u64,
+        #[arg(long)]
+        debt_amount: u64,
+        #[command(flatten)]
+        price_feeds: PriceFeedArgs,
+    },
+    /// Repay all debt and withdraw all collateral
+    ClosePosition {
+        #[arg(long)]
+        mint: Pubkey,
+        #[command(flatten)]
+        price_feeds: PriceFeedArgs,
+    },
+    /// Add or remove collateral and debt, negative deltas withdraw or repay
+    AdjustPosition {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long, allow_hyphen_values = true, default_value_t = 0)]
+        collateral_delta: i64,
+        #[arg(long, allow_hyphen_values = true, default_value_t = 0)]
+        debt_delta: i64,
+        #[command(flatten)]
+        price_feeds: PriceFeedArgs,
+    },
+    /// Deposit stablecoins into a collateral's stability pool
+    Stake {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        amount: u64,
+    },
+    /// Withdraw a whole stability pool deposit with its gains
+    Unstake {
+        #[arg(long)]
+        mint: Pubkey,
+    },
+    /// Claim stability pool gains
+    Claim {
+        #[arg(long)]
+        mint: Pubkey,
+    },
+    /// Accrue interest and update the rate from the stablecoin price
+    UpdateRate {
+        /// Price account of the stablecoin feed, derived from the config unless given
+        #[arg(long)]
+        price_feed: Option<Pubkey>,
+    },
+    /// Mint accrued interest, split between the treasury and the stability pool
+    CollectInterest,
+    /// Liquidate an unhealthy position through the stability pool
+    Liquidate {
+        #[arg(long)]
+        user: Pubkey,
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        debt_amount: u64,
+        #[command(flatten)]
+        price_feeds: PriceFeedArgs,
+    },
+    /// Redeem stablecoins for collateral against the riskiest positions
+    Redeem {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        amount: u64,
+        #[command(flatten)]
+        price_feeds: PriceFeedArgs,
+    },
+    /// Start a Dutch auction for an unhealthy position
+    StartAuction {
+        #[arg(long)]
+        user: Pubkey,
+        #[arg(long)]
+        mint: Pubkey,
+        #[command(flatten)]
+        price_feeds: PriceFeedArgs,
+    },
+    /// Buy collateral from a running auction
+    BidAuction {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        auction_id: u64,
+        #[arg(long)]
+        collateral_amount: u64,
+    },
+    /// Restart an auction that decayed to its floor from the current oracle price
+    ResetAuction {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        auction_id: u64,
+        #[command(flatten)]
+        price_feeds: PriceFeedArgs,
+    },
+    /// Propose a new admin, who has to accept
+    ProposeAdmin {
+        #[arg(long)]
+        new_admin: Pubkey,
+    },
+    /// Accept a pending admin proposal with the signing keypair
+    AcceptAdmin,
+    /// Set the key allowed to pause the protocol
+    SetGuardian {
+        #[arg(long)]
+        guardian: Pubkey,
+    },
+    /// Set a collateral's debt ceiling and minimum debt
+    SetDebtLimits {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        debt_ceiling: u64,
+        #[arg(long)]
+        min_debt: u64,
+    },
+    /// Pause exactly the listed actions, protocol wide or for one collateral
+    Pause {
+        #[arg(long)]
+        mint: Option<Pubkey>,
+        /// Actions to pause, none unpauses everything
+        #[arg(long, value_enum, value_delimiter = ',')]
+        actions: Vec<PauseActionArg>,
+    },
+    /// Replace a collateral's price feed and oracle settings
+    UpdateCollateralOracle {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        feed: String,
+        #[command(flatten)]
+        oracle: OracleArgs,
+        #[command(flatten)]
+        secondary_oracle: SecondaryOracleArgs,
+        #[command(flatten)]
+        price_feeds: PriceFeedArgs,
+    },
+    /// Replace the stablecoin price feed and oracle settings
+    UpdateStablecoinOracle {
+        #[arg(long)]
+        feed: String,
+        #[command(flatten)]
+        oracle: OracleArgs,
+        /// Price account of the new feed, derived from the feed id unless given
+        #[arg(long)]
+        price_feed: Option<Pubkey>,
+    },
+    /// Publish a mock price, for programs built with the mock-oracle feature
+    #[cfg(feature = "mock-oracle")]
+    SetMockPrice {
+        #[arg(long)]
+        feed: String,
+        #[arg(long, allow_hyphen_values = true)]
+        price: i64,
+        /// Defaults to the spot price
+        #[arg(long, allow_hyphen_values = true)]
+        ema_price: Option<i64>,
+        #[arg(long, default_value_t = 0)]
+        conf: u64,
+        #[arg(long, allow_hyphen_values = true, default_value_t = -8)]
+        exponent: i32,
+    },
+    /// Print the protocol config
+    ShowConfig,
+    /// Print a collateral config
+    ShowCollateral {
+        #[arg(long)]
+        mint: Pubkey,
+    },
+    /// Print a position with its current debt and LTV
+    ShowPosition {
+        #[arg(long)]
+        mint: Pubkey,
+        /// Defaults to the signing keypair
+        #[arg(long)]
+        user: Option<Pubkey>,
+        #[command(flatten)]
+        price_feeds: PriceFeedArgs,
+    },
+    /// Print a stability pool deposit with its pending gains
+    ShowStake {
+        #[arg(long)]
+        mint: Pubkey,
+        /// Defaults to the signing keypair
+        #[arg(long)]
+        user: Option<Pubkey>,
+    },
+    /// Print an auction with its current price
+    ShowAuction {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        auction_id: u64,
+    },
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let context = Context::new(&cli.url, &cli.keypair)?;
+    let payer = context.pubkey();
+
+    match cli.command {
+        Command::InitConfig {
+            protocol_fee,
+            redemption_fee,
+            mint_fee,
+            base_rate,
+            sigma,
+            debt_ceiling,
+            stablecoin_feed,
+            oracle,
+            price_feed,
+        } => {
+            // The program reads the feed before storing it
+            let stablecoin_oracle = OracleConfig::from(&oracle);
+            let price_feed = context.new_stablecoin_price_feed(
+                &stablecoin_feed,
+                &stablecoin_oracle,
+                price_feed,
+            )?;
+
+            context.send(instructions::initialize_protocol_config(
+                &payer,
+                &price_feed,
+                protocol_fee,
+                redemption_fee,
+                mint_fee,
+                base_rate,
+                sigma,
+                debt_ceiling,
+                stablecoin_feed,
+                stablecoin_oracle,
+            ))
+        }
+        Command::UpdateConfig {
+            protocol_fee,
+            redemption_fee,
+            mint_fee,
+            base_rate,
+            sigma,
+            debt_ceiling,
+            price_feed,
+        } => context.send(instructions::update_protocol_config(
+            &payer,
+            &context.stablecoin_price_feed(price_feed)?,
+            protocol_fee,
+            redemption_fee,
+            mint_fee,
+            base_rate,
+            sigma,
+            debt_ceiling,
+        )),
+        Command::ListCollateral {
+            mint,
+            feed,
+            oracle,
+            secondary_oracle,
+            price_feeds,
+            max_ltv,
+            liquidation_threshold,
+            liquidation_penalty,
+            close_factor,
+            liquidator_reward,
+            liquidation_mode,
+            auction,
+            debt_ceiling,
+            min_debt,
+        } => {
+            // The collateral config does not exist yet, so the price accounts are derived
+            // from the arguments
+            let collateral_oracle = OracleConfig::from(&oracle);
+            let secondary_oracle = secondary_oracle.secondary_oracle()?;
+            let (price_feed, secondary_price_feed) = context.new_price_feeds(
+                &feed,
+                &collateral_oracle,
+                secondary_oracle.as_ref(),
+                &price_feeds,
+            )?;
+
+            context.send(instructions::initialize_collateral_vault(
+                &payer,
+                &mint,
+                &price_feed,
+                secondary_price_feed,
+                feed,
+                collateral_oracle,
+                secondary_oracle,
+                max_ltv,
+                liquidation_threshold,
+                liquidation_penalty,
+                close_factor,
+                liquidator_reward,
+                liquidation_mode.into(),
+                (&auction).into(),
+                debt_ceiling,
+                min_debt,
+            ))
+        }
+        Command::OpenPosition {
+            mint,
+            collateral_amount,
+            debt_amount,
+            price_feeds,
+        } => {
+            let protocol_config: ProtocolConfig = context.fetch(&pda::protocol_config())?;
+            let collateral_config = context.collateral_config(&mint)?;
+            let (price_feed, secondary_price_feed) =
+                context.price_feeds(&collateral_config, &price_feeds)?;
+
+            let opened = Position {
+                user: payer,
+                collateral_amount,
+                debt_amount: debt_amount + protocol_config.calculate_mint_fee(debt_amount)?,
+                prev_cumulative_interest_rate: protocol_config.cumulative_interest_rate,
+                redistributed_collateral_snapshot: collateral_config
+                    .redistributed_collateral_per_unit,
+                redistributed_debt_snapshot: collateral_config.redistributed_debt_per_unit,
+                prev_position: None,
+                next_position: None,
+            };
+            let positions =
+                context.position_accounts(&mint, &collateral_config, &payer, Some(&opened))?;
+
+            context.send(instructions::open_position(
+                &payer,
+                &mint,
+                &price_feed,
+                secondary_price_feed,
+                collateral_amount,
+                debt_amount,
+                &positions,
+            ))
+        }
+        Command::ClosePosition { mint, price_feeds } => {
+            let collateral_config = context.collateral_config(&mint)?;
+            let (price_feed, secondary_price_feed) =
+                context.price_feeds(&collateral_config, &price_feeds)?;
+
+            let positions = context.position_accounts(&mint, &collateral_config, &payer, None)?;
+
+            context.send(instructions::close_position(
+                &payer,
+                &mint,
+                &price_feed,
+                secondary_price_feed,
+                &positions,
+            ))
+        }
+        Command::AdjustPosition {
+            mint,
+            collateral_delta,
+            debt_delta,
+            price_feeds,
+        } => {
+            let protocol_config: ProtocolConfig = context.fetch(&pda::protocol_config())?;
+            let collateral_config = context.collateral_config(&mint)?;
+            let (price_feed, secondary_price_feed) =
+                context.price_feeds(&collateral_config, &price_feeds)?;
+
+            let position: Position = context.fetch(&pda::position(&payer, &mint))?;
+            let mut adjusted =
+                health::settle_position(&position, &collateral_config, &protocol_config)?;
+            adjusted.collateral_amount = adjusted
+                .collateral_amount
+                .saturating_add_signed(collateral_delta);
+            adjusted.debt_amount = adjusted.debt_amount.saturating_add_signed(debt_delta);
+            if debt_delta > 0 {
+                adjusted.debt_amount +=
+                    protocol_config.calculate_mint_fee(debt_delta.unsigned_abs())?;
+            }
+            let positions =
+                context.position_accounts(&mint, &collateral_config, &payer, Some(&adjusted))?;
+
+            context.send(instructions::adjust_position(
+                &payer,
+                &mint,
+                &price_feed,
+                secondary_price_feed,
+                collateral_delta,
+                debt_delta,
+                &positions,
+            ))
+        }
+        Command::Stake { mint, amount } => {
+            context.send(instructions::stake_stable_tokens(&payer, &mint, amount))
+        }
+        Command::Unstake { mint } => {
+            context.send(instructions::unstake_stable_tokens(&payer, &mint))
+        }
+        Command::Claim { mint } => context.send(instructions::claim_stake_reward(&payer, &mint)),
+        Command::UpdateRate { price_feed } => context.send(instructions::update_interest_rate(
+            &payer,
+            &context.stablecoin_price_feed(price_feed)?,
+        )),
+        Command::CollectInterest => context.send(instructions::collect_interest(&payer)),
+        Command::Liquidate {
+            user,
+            mint,
+            debt_amount,
+            price_feeds,
+        } => {
+            let protocol_config: ProtocolConfig = context.fetch(&pda::protocol_config())?;
+            let collateral_config = context.collateral_config(&mint)?;
+            let (price_feed, secondary_price_feed) =
+                context.price_feeds(&collateral_config, &price_feeds)?;
+            let price = context.collateral_price(&collateral_config, &price_feed)?;
+            let decimals = context.fetch::<Mint>(&mint)?.decimals;
+
+            let position: Position = context.fetch(&pda::position(&user, &mint))?;
+            let liquidated = health::liquidated_position(
+                &health::settle_position(&position, &collateral_config, &protocol_config)?,
+                &collateral_config,
+                &price,
+                decimals,
+                debt_amount,
+            )?;
+            let positions =
+                context.position_accounts(&mint, &collateral_config, &user, Some(&liquidated))?;
+
+            context.send(instructions::liquidate_position(
+                &payer,
+                &user,
+                &mint,
+                &price_feed,
+                secondary_price_feed,
+                debt_amount,
+                &positions,
+            ))
+        }
+        Command::Redeem {
+            mint,
+            amount,
+            price_feeds,
+        } => {
+            let protocol_config: ProtocolConfig = context.fetch(&pda::protocol_config())?;
+            let collateral_config = context.collateral_config(&mint)?;
+            let (price_feed, secondary_price_feed) =
+                context.price_feeds(&collateral_config, &price_feeds)?;
+            let price = context.collateral_price(&collateral_config, &price_feed)?;
+            let decimals = context.fetch::<Mint>(&mint)?.decimals;
+
+            let positions = position_list::redemption_accounts(
+                &rpc::collateral_positions(&context.rpc, &mint)?,
+                &collateral_config,
+                &protocol_config,
+                amount,
+                &price,
+                decimals,
+            )?;
+
+            context.send(instructions::redeem(
+                &payer,
+                &mint,
+                &price_feed,
+                secondary_price_feed,
+                amount,
+                &positions,
+            ))
+        }
+        Command::StartAuction {
+            user,
+            mint,
+            price_feeds,
+        } => {
+            let collateral_config = context.collateral_config(&mint)?;
+            let (price_feed, secondary_price_feed) =
+                context.price_feeds(&collateral_config, &price_feeds)?;
+
+            let positions = context.position_accounts(&mint, &collateral_config, &user, None)?;
+
+            println!("Auction id: {}", collateral_config.auction_count);
+            context.send(instructions::start_auction(
+                &payer,
+                &user,
+                &mint,
+                collateral_config.auction_count,
+                &price_feed,
+                secondary_price_feed,
+                &positions,
+            ))
+        }
+        Command::BidAuction {
+            mint,
+            auction_id,
+            collateral_amount,
+        } => {
+            let auction: Auction = context.fetch(&pda::auction(&mint, auction_id))?;
+
+            context.send(instructions::bid_auction(
+                &payer,
+                &auction,
+                collateral_amount,
+            ))
+        }
+        Command::ResetAuction {
+            mint,
+            auction_id,
+            price_feeds,
+        } => {
+            let collateral_config = context.collateral_config(&mint)?;
+            let (price_feed, secondary_price_feed) =
+                context.price_feeds(&collateral_config, &price_feeds)?;
+            let auction: Auction = context.fetch(&pda::auction(&mint, auction_id))?;
+
+            context.send(instructions::reset_auction(
+                &payer,
+                &auction,
+                &price_feed,
+                secondary_price_feed,
+            ))
+        }
+        Command::ProposeAdmin { new_admin } => {
+            context.send(instructions::propose_admin(&payer, new_admin))
+        }
+        Command::AcceptAdmin => context.send(instructions::accept_admin(&payer)),
+        Command::SetGuardian { guardian } => {
+            context.send(instructions::set_guardian(&payer, guardian))
+        }
+        Command::SetDebtLimits {
+            mint,
+            debt_ceiling,
+            min_debt,
+        } => context.send(instructions::set_collateral_debt_limits(
+            &payer,
+            &mint,
+            debt_ceiling,
+            min_debt,
+        )),
+        Command::Pause { mint, actions } => {
+            let paused = pause_flags(&actions);
+
+            context.send(match mint {
+                Some(mint) => instructions::set_collateral_pause(&payer, &mint, paused),
+                None => instructions::set_protocol_pause(&payer, paused),
+            })
+        }
+        Command::UpdateCollateralOracle {
+            mint,
+            feed,
+            oracle,
+            secondary_oracle,
+            price_feeds,
+        } => {
+            // The program reads the new feeds before storing them
+            let collateral_oracle = OracleConfig::from(&oracle);
+            let secondary_oracle = secondary_oracle.secondary_oracle()?;
+            let (price_feed, secondary_price_feed) = context.new_price_feeds(
+                &feed,
+                &collateral_oracle,
+                secondary_oracle.as_ref(),
+                &price_feeds,
+            )?;
+
+            context.send(instructions::update_collateral_oracle(
+                &payer,
+                &mint,
+                &price_feed,
+                secondary_price_feed,
+                feed,
+                collateral_oracle,
+                secondary_oracle,
+            ))
+        }
+        Command::UpdateStablecoinOracle {
+            feed,
+            oracle,
+            price_feed,
+        } => {
+            let stablecoin_oracle = OracleConfig::from(&oracle);
+            let price_feed =
+                context.new_stablecoin_price_feed(&feed, &stablecoin_oracle, price_feed)?;
+
+            context.send(instructions::update_stablecoin_oracle(
+                &payer,
+                &price_feed,
+                feed,
+                stablecoin_oracle,
+            ))
+        }
+        #[cfg(feature = "mock-oracle")]
+        Command::SetMockPrice {
+            feed,
+            price,
+            ema_price,
+            conf,
+            exponent,
+        } => context.send(instructions::set_mock_price(
+            &payer,
+            parse_feed_id(&feed)?,
+            price,
+            ema_price.unwrap_or(price),
+            conf,
+            exponent,
+        )),
+        Command::ShowConfig => show::show_config(&context),
+        Command::ShowCollateral { mint } => show::show_collateral(&context, &mint),
+        Command::ShowPosition {
+            mint,
+            user,
+            price_feeds,
+        } => show::show_position(&context, &mint, &user.unwrap_or(payer), &price_feeds),
+        Command::ShowStake { mint, user } => {
+            show::show_stake(&context, &mint, &user.unwrap_or(payer))
+        }
+        Command::ShowAuction { mint, auction_id } => {
+            show::show_auction(&context, &mint, auction_id)
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token::Mint;
+use cdp_client::{
+    accounts::{Auction, Position, ProtocolConfig, StakeAccount},
+    health, pda,
+};
+use cdp_stablecoin_protocol::{
+    constants::{BPS_SCALE, INTEREST_SCALE, STABLE_DECIMALS},
+    state::PauseFlags,
+};
+
+use crate::{args::PriceFeedArgs, context::Context};
+
+// Fixed point amount with decimals digits after the point
+fn units(amount: u128, decimals: u8) -> String {
+    let scale = 10_u128.pow(decimals as u32);
+
+    format!(
+        "{}.{:0width$}",
+        amount / scale,
+        amount % scale,
+        width = decimals as usize
+    )
+}
+
+// Oracle price of price * 10^exponent, the exponent can have either sign
+fn price(price: u128, exponent: i32) -> String {
+    if exponent < 0 {
+        units(price, exponent.unsigned_abs() as u8)
+    } else {
+        (price * 10_u128.pow(exponent as u32)).to_string()
+    }
+}
+
+fn bps(value: u128) -> String {
+    if value == u128::MAX {
+        return "n/a".to_string();
+    }
+
+    format!("{}%", units(value * 100, 4))
+}
+
+// Yearly rate in INTEREST_SCALE as a percentage
+fn rate(value: u128) -> String {
+    bps(value * BPS_SCALE as u128 / INTEREST_SCALE)
+}
+
+fn stable(amount: u128) -> String {
+    units(amount, STABLE_DECIMALS)
+}
+
+fn paused(flags: &PauseFlags) -> String {
+    let actions = [
+        ("open", flags.open),
+        ("repay", flags.repay),
+        ("liquidate", flags.liquidate),
+        ("redeem", flags.redeem),
+        ("stake", flags.stake),
+        ("unstake", flags.unstake),
+        ("claim", flags.claim),
+        ("interest-update", flags.interest_update),
+    ]
+    .iter()
+    .filter(|(_, paused)| *paused)
+    .map(|(action, _)| *action)
+    .collect::<Vec<_>>();
+
+    if actions.is_empty() {
+        "none".to_string()
+    } else {
+        actions.join(", ")
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn show_config(context: &Context) -> anyhow::Result<()> {
+    let config: ProtocolConfig = context.fetch(&pda::protocol_config())?;
+
+    println!("Protocol config {}", pda::protocol_config());
+    println!("  admin:                 {}", config.admin);
+    if let Some(pending_admin) = config.pending_admin {
+        println!("  pending admin:         {}", pending_admin);
+    }
+    println!("  guardian:              {}", config.guardian);
+    println!("  paused:                {}", paused(&config.paused));
+    println!("  stable mint:           {}", config.stable_mint);
+    println!(
+        "  protocol fee:          {}",
+        bps(config.protocol_fee as u128)
+    );
+    println!(
+        "  redemption fee:        {}",
+        bps(config.redemption_fee as u128)
+    );
+    println!("  mint fee:              {}", bps(config.mint_fee as u128));
+    println!("  base rate:             {}", bps(config.base_rate as u128));
+    println!("  sigma:                 {}", bps(config.sigma as u128));
+    println!("  interest rate:         {}", rate(config.interest_rate));
+    println!(
+        "  cumulative index:      {}",
+        units(config.cumulative_interest_rate, 18)
+    );
+    println!(
+        "  last rate update:      {}",
+        config.last_interest_rate_update
+    );
+    println!(
+        "  stablecoin feed:       {}",
+        hex(&config.stablecoin_price_feed)
+    );
+    println!("  total debt:            {}", stable(config.total_debt));
+    println!(
+        "  debt ceiling:          {}",
+        stable(config.debt_ceiling as u128)
+    );
+    println!(
+        "  accrued interest:      {}",
+        stable(config.accrued_interest)
+    );
+    println!(
+        "  total staked:          {}",
+        stable(config.total_stake_amount)
+    );
+    println!(
+        "  depletion factor:      {}",
+        bps(config.deposit_depletion_factor as u128)
+    );
+    println!("  stake epoch:           {}", config.stake_epoch);
+
+    Ok(())
+}
+
+pub fn show_collateral(context: &Context, collateral_mint: &Pubkey) -> anyhow::Result<()> {
+    let protocol_config: ProtocolConfig = context.fetch(&pda::protocol_config())?;
+    let config = context.collateral_config(collateral_mint)?;
+    let mint: Mint = context.fetch(collateral_mint)?;
+
+    let current_debt = config.current_debt(protocol_config.cumulative_interest_rate)?;
+
+    println!(
+        "Collateral config {}",
+        pda::collateral_vault_config(collateral_mint)
+    );
+    println!("  mint:                  {}", config.mint);
+    println!("  vault:                 {}", config.vault);
+    println!("  paused:                {}", paused(&config.paused));
+    println!(
+        "  price feed:            {}",
+        hex(&config.collateral_price_feed)
+    );
+    if let Some(secondary_oracle) = config.secondary_oracle {
+        println!(
+            "  secondary feed:        {} (max deviation {})",
+            hex(&secondary_oracle.price_feed),
+            bps(secondary_oracle.max_deviation as u128)
+        );
+    }
+    println!(
+        "  collateral:            {}",
+        units(config.collateral_amount as u128, mint.decimals)
+    );
+    println!("  debt:                  {}", stable(current_debt));
+    println!(
+        "  bad debt:              {}",
+        stable(config.bad_debt as u128)
+    );
+    println!(
+        "  debt ceiling:          {}",
+        stable(config.debt_ceiling as u128)
+    );
+    println!(
+        "  min debt:              {}",
+        stable(config.min_debt as u128)
+    );
+    println!("  max ltv:               {}", bps(config.max_ltv as u128));
+    println!(
+        "  liquidation threshold: {}",
+        bps(config.liquidation_threshold as u128)
+    );
+    println!(
+        "  liquidation penalty:   {}",
+        bps(config.liquidation_penalty as u128)
+    );
+    println!(
+        "  close factor:          {}",
+        bps(config.close_factor as u128)
+    );
+    println!(
+        "  liquidator reward:     {}",
+        bps(config.liquidator_reward as u128)
+    );
+    println!(
+        "  pool rewards:          {}",
+        units(config.stability_pool_rewards_amount as u128, mint.decimals)
+    );
+    println!("  auctions started:      {}", config.auction_count);
+
+    Ok(())
+}
+
+pub fn show_position(
+    context: &Context,
+    collateral_mint: &Pubkey,
+    user: &Pubkey,
+    price_feeds: &PriceFeedArgs,
+) -> anyhow::Result<()> {
+    let protocol_config: ProtocolConfig = context.fetch(&pda::protocol_config())?;
+    let collateral_config = context.collateral_config(collateral_mint)?;
+    let mint: Mint = context.fetch(collateral_mint)?;
+    let position: Position = context.fetch(&pda::position(user, collateral_mint))?;
+
+    let settled = health::settle_position(&position, &collateral_config, &protocol_config)?;
+
+    println!("Position {}", pda::position(user, collateral_mint));
+    println!("  user:                  {}", position.user);
+    println!(
+        "  collateral:            {}",
+        units(settled.collateral_amount as u128, mint.decimals)
+    );
+    println!(
+        "  recorded debt:         {}",
+        stable(position.debt_amount as u128)
+    );
+    println!(
+        "  current debt:          {}",
+        stable(settled.debt_amount as u128)
+    );
+
+    // Health needs a price, which is optional for a read command
+    let price = context
+        .price_feeds(&collateral_config, price_feeds)
+        .and_then(|(price_feed, _)| context.collateral_price(&collateral_config, &price_feed));
+
+    match price {
+        Ok(price) => {
+            let position_health = health::position_health(
+                &position,
+                &collateral_config,
+                &protocol_config,
+                &price,
+                mint.decimals,
+            )?;
+
+            println!(
+                "  collateral value:      {}",
+                stable(position_health.collateral_value)
+            );
+            println!("  ltv:                   {}", bps(position_health.ltv));
+            println!(
+                "  liquidatable:          {}",
+                position_health.is_liquidatable(&collateral_config)
+            );
+        }
+        Err(error) => println!("  ltv:                   n/a ({})", error),
+    }
+
+    Ok(())
+}
+
+pub fn show_stake(
+    context: &Context,
+    collateral_mint: &Pubkey,
+    user: &Pubkey,
+) -> anyhow::Result<()> {
+    let protocol_config: ProtocolConfig = context.fetch(&pda::protocol_config())?;
+    let collateral_config = context.collateral_config(collateral_mint)?;
+    let mint: Mint = context.fetch(collateral_mint)?;
+    let stake: StakeAccount = context.fetch(&pda::stake_account(user, collateral_mint))?;
+
+    println!(
+        "Stake account {}",
+        pda::stake_account(user, collateral_mint)
+    );
+    println!("  user:                  {}", stake.user);
+    println!("  deposited:             {}", stable(stake.amount as u128));
+    println!(
+        "  remaining deposit:     {}",
+        stable(stake.compounded_amount(&protocol_config)? as u128)
+    );
+    println!(
+        "  collateral gain:       {}",
+        units(
+            stake.collateral_gain(&collateral_config)? as u128,
+            mint.decimals
+        )
+    );
+    println!(
+        "  interest gain:         {}",
+        stable(stake.interest_gain(&protocol_config)? as u128)
+    );
+    println!("  epoch:                 {}", stake.epoch);
+    println!("  last staked:           {}", stake.last_staked);
+
+    Ok(())
+}
+
+pub fn show_auction(
+    context: &Context,
+    collateral_mint: &Pubkey,
+    auction_id: u64,
+) -> anyhow::Result<()> {
+    let protocol_config: ProtocolConfig = context.fetch(&pda::protocol_config())?;
+    let mint: Mint = context.fetch(collateral_mint)?;
+    let auction: Auction = context.fetch(&pda::auction(collateral_mint, auction_id))?;
+
+    let now = context.rpc.get_block_time(context.rpc.get_slot()?)?;
+
+    println!("Auction {}", pda::auction(collateral_mint, auction_id));
+    println!("  owner:                 {}", auction.owner);
+    println!("  keeper:                {}", auction.keeper);
+    println!(
+        "  collateral:            {}",
+        units(auction.collateral_amount as u128, mint.decimals)
+    );
+    println!(
+        "  current debt:          {}",
+        stable(auction.calculate_current_debt(protocol_config.cumulative_interest_rate)? as u128)
+    );
+    println!(
+        "  current price:         {}",
+        price(auction.current_price(now)? as u128, auction.price_exponent)
+    );
+    println!(
+        "  floor price:           {}",
+        price(auction.floor_price as u128, auction.price_exponent)
+    );
+    println!("  start time:            {}", auction.start_time);
+
+    Ok(())
+}
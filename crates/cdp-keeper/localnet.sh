@@ -0,0 +1,90 @@
+#!/usr/bin/env bash
+# Runs the keeper against a local validator with mock prices.
+#
+# Builds the program, cdp-cli and cdp-keeper with the mock-oracle feature, starts a fresh
+# solana-test-validator with the program deployed, lists a collateral priced by a MockPrice
+# account and opens a position backed by a stability pool deposit. It then drops the mock
+# price so the position is liquidatable and runs a single keeper pass, which has to
+# liquidate it.
+#
+# Needs anchor, solana, solana-test-validator and spl-token on the PATH. Run it from
+# anywhere:
+#
+#   crates/cdp-keeper/localnet.sh
+#
+# The same flow works by hand against any validator running a mock-oracle build:
+#
+#   cargo build -p cdp-cli -p cdp-keeper --features cdp-cli/mock-oracle,cdp-keeper/mock-oracle
+#   cdp-cli set-mock-price --feed <FEED_ID> --price <PRICE>
+#   cdp-keeper --once
+set -euo pipefail
+
+cd "$(dirname "$0")/../.."
+
+PROGRAM_ID=3xYBiBikqqFRLKJbctJ1ByaKr1cHGbBdhj9BSUTuTECa
+URL=http://127.0.0.1:8899
+STABLECOIN_FEED=eaa020c61cc479712813461ce153894a96a6c00b21ed0cfc2798d1f9a9e9c94a
+COLLATERAL_FEED=ef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d
+
+WORK_DIR=$(mktemp -d)
+KEYPAIR=$WORK_DIR/admin.json
+CONFIG=$WORK_DIR/config.yml
+
+anchor build -- --features mock-oracle
+cargo build -p cdp-cli -p cdp-keeper --features cdp-cli/mock-oracle,cdp-keeper/mock-oracle
+
+solana-keygen new --no-bip39-passphrase --silent --outfile "$KEYPAIR"
+solana-keygen new --no-bip39-passphrase --silent --outfile "$WORK_DIR/mint.json"
+solana config set --config "$CONFIG" --url "$URL" --keypair "$KEYPAIR" > /dev/null
+
+# The admin keypair is the upgrade authority, which initialize_protocol_config and
+# set_mock_price require
+solana-test-validator --reset --quiet --ledger "$WORK_DIR/ledger" \
+    --upgradeable-program "$PROGRAM_ID" target/deploy/cdp_stablecoin_protocol.so "$KEYPAIR" &
+VALIDATOR=$!
+trap 'kill $VALIDATOR; rm -rf "$WORK_DIR"' EXIT
+
+until solana --config "$CONFIG" cluster-version > /dev/null 2>&1; do
+    sleep 1
+done
+
+solana --config "$CONFIG" airdrop 100 > /dev/null
+
+cli() {
+    target/debug/cdp-cli --url "$URL" --keypair "$KEYPAIR" "$@"
+}
+
+# $1 stablecoin and $200 collateral, with 8 decimal exponents. init-config reads the
+# stablecoin price, so it is set first
+cli set-mock-price --feed "$STABLECOIN_FEED" --price 100000000
+cli set-mock-price --feed "$COLLATERAL_FEED" --price 20000000000
+
+cli init-config --protocol-fee 500 --redemption-fee 500 --mint-fee 500 --base-rate 500 \
+    --sigma 200 --debt-ceiling 1000000000000 --stablecoin-feed "$STABLECOIN_FEED" --source mock
+
+MINT=$(solana-keygen pubkey "$WORK_DIR/mint.json")
+spl-token --config "$CONFIG" create-token --decimals 6 "$WORK_DIR/mint.json" > /dev/null
+spl-token --config "$CONFIG" create-account "$MINT" > /dev/null
+spl-token --config "$CONFIG" mint "$MINT" 10 > /dev/null
+
+cli list-collateral --mint "$MINT" --feed "$COLLATERAL_FEED" --source mock --max-ltv 8000 \
+    --liquidation-threshold 8500 --liquidation-penalty 500 --close-factor 5000 \
+    --liquidator-reward 100 --debt-ceiling 1000000000000 --min-debt 1000000
+
+# 1 token for 100 stablecoins, all of which back the stability pool
+cli open-position --mint "$MINT" --collateral-amount 1000000 --debt-amount 100000000
+cli stake --mint "$MINT" --amount 100000000
+
+# At $120 the 105 stablecoins of debt are above the 85% threshold
+cli set-mock-price --feed "$COLLATERAL_FEED" --price 12000000000
+
+OUTPUT=$(target/debug/cdp-keeper --url "$URL" --keypair "$KEYPAIR" --once)
+echo "$OUTPUT"
+
+if ! grep -q '"event":"liquidate_position"' <<< "$OUTPUT" \
+    || grep -q '"event":"liquidate_position_failed"' <<< "$OUTPUT"; then
+    echo "keeper did not liquidate the position" >&2
+    exit 1
+fi
+
+cli show-position --mint "$MINT"
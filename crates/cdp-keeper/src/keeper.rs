@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+
+use anchor_lang::prelude::Pubkey;
+use cdp_client::{
+    accounts::{self, Auction, CollateralConfig, ProtocolConfig},
+    health, instructions,
+    oracle::price_feed_address,
+    pda, position_list, rpc,
+};
+use cdp_stablecoin_protocol::state::{LiquidationMode, PauseAction};
+use serde_json::json;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_sdk::{instruction::Instruction, signature::Keypair, signer::Signer};
+
+use crate::{
+    log,
+    scan::{self, Candidate, Market, PriceFeedOverrides},
+};
+
+pub struct Keeper {
+    pub rpc: RpcClient,
+    pub payer: Keypair,
+    pub price_feeds: PriceFeedOverrides,
+    pub stablecoin_price_feed: Option<Pubkey>,
+    // Seconds between interest rate updates
+    pub rate_interval: i64,
+    // Log what would be sent without sending it
+    pub dry_run: bool,
+}
+
+impl Keeper {
+    // Send unless in dry-run mode, logging the outcome either way
+    fn submit(&self, event: &str, instruction: Instruction, fields: serde_json::Value) -> bool {
+        if self.dry_run {
+            log::info(event, json!({ "dry_run": true, "details": fields }));
+            return true;
+        }
+
+        match rpc::send_instruction(&self.rpc, &self.payer, instruction) {
+            Ok(signature) => {
+                log::info(
+                    event,
+                    json!({ "signature": signature.to_string(), "details": fields }),
+                );
+                true
+            }
+            Err(error) => {
+                log::error(
+                    &format!("{}_failed", event),
+                    json!({ "error": error.to_string(), "details": fields }),
+                );
+                false
+            }
+        }
+    }
+
+    // One pass: crank the interest rate when due, liquidate every unhealthy position and
+    // restart auctions stuck at their floor
+    pub fn tick(&self) -> anyhow::Result<()> {
+        let protocol_config: ProtocolConfig =
+            cdp_client::accounts::decode(&self.rpc.get_account_data(&pda::protocol_config())?)?;
+        let now = self.rpc.get_block_time(self.rpc.get_slot()?)?;
+
+        self.crank_interest_rate(&protocol_config, now)?;
+
+        let markets = scan::load_markets(&self.rpc, &self.price_feeds, now)?;
+        let candidates = scan::find_candidates(&self.rpc, &protocol_config, &markets)?;
+
+        log::info(
+            "scan",
+            json!({ "markets": markets.len(), "liquidatable": candidates.len() }),
+        );
+
+        if protocol_config
+            .paused
+            .check(PauseAction::Liquidate)
+            .is_err()
+        {
+            log::warn("liquidations_paused", json!({}));
+            return Ok(());
+        }
+
+        // Auction PDAs are numbered by auction_count, which each started auction bumps
+        let mut auction_ids: HashMap<Pubkey, u64> = markets
+            .values()
+            .map(|market| (market.mint, market.config.auction_count))
+            .collect();
+
+        for candidate in &candidates {
+            let market = &markets[&candidate.mint];
+
+            if market.config.paused.check(PauseAction::Liquidate).is_err() {
+                continue;
+            }
+
+            self.liquidate(candidate, market, &protocol_config, &mut auction_ids);
+        }
+
+        self.reset_expired_auctions(&markets, now)
+    }
+
+    fn reset_expired_auctions(
+        &self,
+        markets: &HashMap<Pubkey, Market>,
+        now: i64,
+    ) -> anyhow::Result<()> {
+        for (key, auction) in rpc::program_accounts::<Auction>(&self.rpc)? {
+            if now < auction.start_time.saturating_add(auction.duration) {
+                continue;
+            }
+
+            let Some(market) = markets.get(&auction.collateral_mint) else {
+                continue;
+            };
+
+            if market.config.paused.check(PauseAction::Liquidate).is_err() {
+                continue;
+            }
+
+            self.submit(
+                "reset_auction",
+                instructions::reset_auction(
+                    &self.payer.pubkey(),
+                    &auction,
+                    &market.price_feed,
+                    market.secondary_price_feed,
+                ),
+                json!({
+                    "auction": key.to_string(),
+                    "auction_id": auction.id,
+                    "mint": auction.collateral_mint.to_string(),
+                    "collateral_amount": auction.collateral_amount,
+                    "debt_amount": auction.debt_amount,
+                }),
+            );
+        }
+
+        Ok(())
+    }
+
+    // Positions around the candidate's old and new place in its collateral's list. They are
+    // fetched again for every liquidation, as the ones sent before it moved positions.
+    fn position_accounts(
+        &self,
+        candidate: &Candidate,
+        market: &Market,
+        protocol_config: &ProtocolConfig,
+    ) -> anyhow::Result<Vec<Pubkey>> {
+        let collateral_config: CollateralConfig = accounts::decode(
+            &self
+                .rpc
+                .get_account_data(&pda::collateral_vault_config(&candidate.mint))?,
+        )?;
+        let positions = rpc::collateral_positions(&self.rpc, &candidate.mint)?;
+        let key = pda::position(&candidate.user, &candidate.mint);
+
+        // An auction takes the whole position, a liquidation may leave part of it
+        let liquidated = match (
+            candidate.liquidation_mode,
+            positions
+                .iter()
+                .find(|(position_key, _)| *position_key == key),
+        ) {
+            (LiquidationMode::StabilityPool, Some((_, position))) => {
+                Some(health::liquidated_position(
+                    &health::settle_position(position, &collateral_config, protocol_config)?,
+                    &collateral_config,
+                    &market.price,
+                    market.decimals,
+                    candidate.debt_amount,
+                )?)
+            }
+            _ => None,
+        };
+
+        Ok(position_list::move_accounts(
+            &positions,
+            &collateral_config,
+            &key,
+            liquidated.as_ref(),
+        )?)
+    }
+
+    fn liquidate(
+        &self,
+        candidate: &Candidate,
+        market: &Market,
+        protocol_config: &ProtocolConfig,
+        auction_ids: &mut HashMap<Pubkey, u64>,
+    ) {
+        let mut fields = json!({
+            "user": candidate.user.to_string(),
+            "mint": candidate.mint.to_string(),
+            "position": pda::position(&candidate.user, &candidate.mint).to_string(),
+            "debt_amount": candidate.debt_amount,
+            "collateral_amount": candidate.collateral_amount,
+            "ltv_bps": candidate.ltv.to_string(),
+            "shortfall": candidate.shortfall.to_string(),
+        });
+
+        let positions = match self.position_accounts(candidate, market, protocol_config) {
+            Ok(positions) => positions,
+            Err(error) => {
+                log::error(
+                    "position_accounts_failed",
+                    json!({ "error": error.to_string(), "details": fields }),
+                );
+                return;
+            }
+        };
+
+        match candidate.liquidation_mode {
+            // The program caps the repaid debt at the close factor
+            LiquidationMode::StabilityPool => {
+                self.submit(
+                    "liquidate_position",
+                    instructions::liquidate_position(
+                        &self.payer.pubkey(),
+                        &candidate.user,
+                        &candidate.mint,
+                        &market.price_feed,
+                        market.secondary_price_feed,
+                        candidate.debt_amount,
+                        &positions,
+                    ),
+                    fields,
+                );
+            }
+            LiquidationMode::Auction => {
+                let auction_id = auction_ids[&candidate.mint];
+                fields["auction_id"] = json!(auction_id);
+
+                let started = self.submit(
+                    "start_auction",
+                    instructions::start_auction(
+                        &self.payer.pubkey(),
+                        &candidate.user,
+                        &candidate.mint,
+                        auction_id,
+                        &market.price_feed,
+                        market.secondary_price_feed,
+                        &positions,
+                    ),
+                    fields,
+                );
+
+                if started {
+                    auction_ids.insert(candidate.mint, auction_id + 1);
+                }
+            }
+        }
+    }
+
+    fn crank_interest_rate(
+        &self,
+        protocol_config: &ProtocolConfig,
+        now: i64,
+    ) -> anyhow::Result<()> {
+        let elapsed = now.saturating_sub(protocol_config.last_interest_rate_update);
+
+        if elapsed < self.rate_interval {
+            return Ok(());
+        }
+
+        if protocol_config
+            .paused
+            .check(PauseAction::InterestUpdate)
+            .is_err()
+        {
+            log::warn("interest_update_paused", json!({}));
+            return Ok(());
+        }
+
+        let Some(price_feed) = self.stablecoin_price_feed.or_else(|| {
+            price_feed_address(
+                &protocol_config.stablecoin_price_feed,
+                &protocol_config.stablecoin_oracle,
+            )
+        }) else {
+            log::warn(
+                "interest_update_skipped",
+                json!({ "error": "no stablecoin price account, pass --stablecoin-price-feed" }),
+            );
+            return Ok(());
+        };
+
+        self.submit(
+            "update_interest_rate",
+            instructions::update_interest_rate(&self.payer.pubkey(), &price_feed),
+            json!({ "seconds_since_update": elapsed }),
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Map, Value};
+
+// One JSON object per line on stdout, with the fields merged after timestamp, level and event
+fn emit(level: &str, event: &str, fields: Value) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+
+    let mut line = Map::new();
+    line.insert("timestamp".to_string(), json!(timestamp));
+    line.insert("level".to_string(), json!(level));
+    line.insert("event".to_string(), json!(event));
+    if let Value::Object(fields) = fields {
+        line.extend(fields);
+    }
+
+    println!("{}", Value::Object(line));
+}
+
+pub fn info(event: &str, fields: Value) {
+    emit("info", event, fields);
+}
+
+pub fn warn(event: &str, fields: Value) {
+    emit("warn", event, fields);
+}
+
+pub fn error(event: &str, fields: Value) {
+    emit("error", event, fields);
+}
@@ -0,0 +1,103 @@
+mod keeper;
+mod log;
+mod scan;
+
+use std::{thread, time::Duration};
+
+use anchor_lang::prelude::Pubkey;
+use clap::Parser;
+use keeper::Keeper;
+use scan::PriceFeedOverrides;
+use serde_json::json;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+
+/// Liquidate unhealthy positions, restart stale auctions and keep the interest rate updated
+#[derive(Parser)]
+struct Cli {
+    /// RPC endpoint of the cluster
+    #[arg(long, env = "CDP_KEEPER_URL", default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Keypair that signs and pays for transactions and receives liquidator rewards
+    #[arg(
+        long,
+        env = "CDP_KEEPER_KEYPAIR",
+        default_value = "~/.config/solana/id.json"
+    )]
+    keypair: String,
+    /// Seconds between scans
+    #[arg(long, env = "CDP_KEEPER_INTERVAL", default_value_t = 10)]
+    interval: u64,
+    /// Seconds between interest rate updates
+    #[arg(long, env = "CDP_KEEPER_RATE_INTERVAL", default_value_t = 3600)]
+    rate_interval: i64,
+    /// Price account of a collateral's Pyth feed, as MINT=ACCOUNT
+    #[arg(long, value_parser = parse_mint_account)]
+    price_feed: Vec<(Pubkey, Pubkey)>,
+    /// Price account of a collateral's secondary Pyth feed, as MINT=ACCOUNT
+    #[arg(long, value_parser = parse_mint_account)]
+    secondary_price_feed: Vec<(Pubkey, Pubkey)>,
+    /// Price account of the stablecoin's Pyth feed
+    #[arg(long)]
+    stablecoin_price_feed: Option<Pubkey>,
+    /// Log the transactions that would be sent without sending them
+    #[arg(long, env = "CDP_KEEPER_DRY_RUN")]
+    dry_run: bool,
+    /// Run a single pass and exit
+    #[arg(long)]
+    once: bool,
+}
+
+fn parse_mint_account(value: &str) -> Result<(Pubkey, Pubkey), String> {
+    let (mint, account) = value
+        .split_once('=')
+        .ok_or_else(|| "expected MINT=ACCOUNT".to_string())?;
+
+    Ok((
+        mint.parse().map_err(|_| format!("invalid mint {}", mint))?,
+        account
+            .parse()
+            .map_err(|_| format!("invalid account {}", account))?,
+    ))
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    let keeper = Keeper {
+        rpc: RpcClient::new_with_commitment(cli.url.clone(), CommitmentConfig::confirmed()),
+        payer: cdp_client::rpc::read_keypair(&cli.keypair)?,
+        price_feeds: PriceFeedOverrides {
+            primary: cli.price_feed.into_iter().collect(),
+            secondary: cli.secondary_price_feed.into_iter().collect(),
+        },
+        stablecoin_price_feed: cli.stablecoin_price_feed,
+        rate_interval: cli.rate_interval,
+        dry_run: cli.dry_run,
+    };
+
+    log::info(
+        "started",
+        json!({
+            "url": cli.url,
+            "keeper": solana_sdk::signer::Signer::pubkey(&keeper.payer).to_string(),
+            "dry_run": cli.dry_run,
+        }),
+    );
+
+    loop {
+        // A failed pass is retried on the next one, except in single pass mode
+        if let Err(error) = keeper.tick() {
+            if cli.once {
+                return Err(error);
+            }
+            log::error("tick_failed", json!({ "error": error.to_string() }));
+        }
+
+        if cli.once {
+            return Ok(());
+        }
+
+        thread::sleep(Duration::from_secs(cli.interval));
+    }
+}
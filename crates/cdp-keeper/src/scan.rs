@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+
+use anchor_lang::{prelude::Pubkey, AccountDeserialize};
+use anchor_spl::token::Mint;
+use anyhow::anyhow;
+use cdp_client::{
+    accounts::{CollateralConfig, Position, ProtocolConfig},
+    health,
+    oracle::{guarded_price, price_feed_address, read_price},
+    pda,
+    rpc::program_accounts,
+};
+use cdp_stablecoin_protocol::{
+    oracle::OraclePrice,
+    state::{LiquidationMode, OracleConfig},
+};
+use serde_json::json;
+use solana_rpc_client::rpc_client::RpcClient;
+
+use crate::log;
+
+// A listed collateral with the price accounts its instructions need and the price the
+// program would use, after the same staleness, confidence and secondary checks
+pub struct Market {
+    pub mint: Pubkey,
+    pub config: CollateralConfig,
+    pub decimals: u8,
+    pub price_feed: Pubkey,
+    pub secondary_price_feed: Option<Pubkey>,
+    pub price: OraclePrice,
+}
+
+pub struct Candidate {
+    pub user: Pubkey,
+    pub mint: Pubkey,
+    pub liquidation_mode: LiquidationMode,
+    pub debt_amount: u64,
+    pub collateral_amount: u64,
+    pub ltv: u128,
+    pub shortfall: u128,
+}
+
+// Price accounts of Pyth feeds, which cannot be derived from the feed id, keyed by mint
+#[derive(Default)]
+pub struct PriceFeedOverrides {
+    pub primary: HashMap<Pubkey, Pubkey>,
+    pub secondary: HashMap<Pubkey, Pubkey>,
+}
+
+// Raw price of feed_id in a price account
+fn load_price(
+    rpc: &RpcClient,
+    price_feed: &Pubkey,
+    feed_id: &[u8; 32],
+    oracle_config: &OracleConfig,
+) -> anyhow::Result<OraclePrice> {
+    let price_account = rpc.get_account(price_feed)?;
+
+    Ok(read_price(
+        price_feed,
+        &price_account.owner,
+        &price_account.data,
+        feed_id,
+        oracle_config,
+    )?)
+}
+
+fn load_market(
+    rpc: &RpcClient,
+    config: CollateralConfig,
+    overrides: &PriceFeedOverrides,
+    now: i64,
+) -> anyhow::Result<Market> {
+    let mint = config.mint;
+
+    let price_feed = overrides
+        .primary
+        .get(&mint)
+        .copied()
+        .or_else(|| price_feed_address(&config.collateral_price_feed, &config.collateral_oracle))
+        .ok_or_else(|| anyhow!("no price account, pass --price-feed {}=<account>", mint))?;
+
+    let secondary_price_feed = match config.secondary_oracle {
+        Some(secondary_oracle) => Some(
+            overrides
+                .secondary
+                .get(&mint)
+                .copied()
+                .or_else(|| {
+                    price_feed_address(&secondary_oracle.price_feed, &secondary_oracle.oracle)
+                })
+                .ok_or_else(|| {
+                    anyhow!(
+                        "no secondary price account, pass --secondary-price-feed {}=<account>",
+                        mint
+                    )
+                })?,
+        ),
+        None => None,
+    };
+
+    let primary = load_price(
+        rpc,
+        &price_feed,
+        &config.collateral_price_feed,
+        &config.collateral_oracle,
+    )?;
+
+    // An unreadable secondary is unusable, the primary alone is not trusted then
+    let secondary = match (config.secondary_oracle, secondary_price_feed) {
+        (Some(secondary_oracle), Some(secondary_price_feed)) => load_price(
+            rpc,
+            &secondary_price_feed,
+            &secondary_oracle.price_feed,
+            &secondary_oracle.oracle,
+        )
+        .ok(),
+        _ => None,
+    };
+
+    let price = guarded_price(&config, primary, secondary, now)?
+        .ok_or_else(|| anyhow!("no trusted price, the collateral is frozen"))?;
+
+    let decimals = Mint::try_deserialize(&mut &rpc.get_account_data(&mint)?[..])?.decimals;
+
+    Ok(Market {
+        mint,
+        config,
+        decimals,
+        price_feed,
+        secondary_price_feed,
+        price,
+    })
+}
+
+// Listed collaterals keyed by mint. Collaterals whose price cannot be read or is frozen
+// are logged and left out, so one bad feed does not stop liquidations of the others
+pub fn load_markets(
+    rpc: &RpcClient,
+    overrides: &PriceFeedOverrides,
+    now: i64,
+) -> anyhow::Result<HashMap<Pubkey, Market>> {
+    let mut markets = HashMap::new();
+
+    for (key, config) in program_accounts::<CollateralConfig>(rpc)? {
+        let mint = config.mint;
+
+        match load_market(rpc, config, overrides, now) {
+            Ok(market) => {
+                markets.insert(mint, market);
+            }
+            Err(error) => log::warn(
+                "market_skipped",
+                json!({
+                    "collateral_config": key.to_string(),
+                    "mint": mint.to_string(),
+                    "error": error.to_string(),
+                }),
+            ),
+        }
+    }
+
+    Ok(markets)
+}
+
+// Liquidatable positions, largest shortfall first
+pub fn find_candidates(
+    rpc: &RpcClient,
+    protocol_config: &ProtocolConfig,
+    markets: &HashMap<Pubkey, Market>,
+) -> anyhow::Result<Vec<Candidate>> {
+    rank_candidates(program_accounts::<Position>(rpc)?, protocol_config, markets)
+}
+
+fn rank_candidates(
+    positions: Vec<(Pubkey, Position)>,
+    protocol_config: &ProtocolConfig,
+    markets: &HashMap<Pubkey, Market>,
+) -> anyhow::Result<Vec<Candidate>> {
+    let mut candidates = Vec::new();
+
+    for (key, position) in positions {
+        // Positions do not store their mint, it is recovered from the PDA seeds
+        let Some(market) = markets
+            .values()
+            .find(|market| pda::position(&position.user, &market.mint) == key)
+        else {
+            continue;
+        };
+
+        let position_health = health::position_health(
+            &position,
+            &market.config,
+            protocol_config,
+            &market.price,
+            market.decimals,
+        )?;
+
+        if !position_health.is_liquidatable(&market.config) {
+            continue;
+        }
+
+        candidates.push(Candidate {
+            user: position.user,
+            mint: market.mint,
+            liquidation_mode: market.config.liquidation_mode,
+            debt_amount: position_health.debt_amount,
+            collateral_amount: position_health.collateral_amount,
+            ltv: position_health.ltv,
+            shortfall: position_health.shortfall(&market.config),
+        });
+    }
+
+    candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.shortfall));
+
+    Ok(candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AnchorDeserialize, Space};
+    use cdp_stablecoin_protocol::{constants::INTEREST_SCALE, state::PriceMode};
+
+    use super::*;
+
+    // Accounts with every field zeroed except the ones health checks read
+    fn zeroed<T: AnchorDeserialize>(space: usize) -> T {
+        T::deserialize(&mut &vec![0; space][..]).unwrap()
+    }
+
+    fn protocol_config() -> ProtocolConfig {
+        let mut protocol_config: ProtocolConfig = zeroed(ProtocolConfig::INIT_SPACE);
+        protocol_config.cumulative_interest_rate = INTEREST_SCALE;
+        protocol_config
+    }
+
+    // $1 per token of 6 decimals, liquidated above 85% LTV
+    fn market(mint: Pubkey) -> Market {
+        let mut config: CollateralConfig = zeroed(CollateralConfig::INIT_SPACE);
+        config.mint = mint;
+        config.liquidation_threshold = 8_500;
+
+        Market {
+            mint,
+            config,
+            decimals: 6,
+            price_feed: Pubkey::new_unique(),
+            secondary_price_feed: None,
+            price: OraclePrice {
+                price: 100_000_000,
+                conf: 0,
+                ema_price: 100_000_000,
+                ema_conf: 0,
+                exponent: -8,
+                publish_time: 0,
+                mode: PriceMode::Spot,
+            },
+        }
+    }
+
+    fn position(mint: &Pubkey, collateral_amount: u64, debt_amount: u64) -> (Pubkey, Position) {
+        let user = Pubkey::new_unique();
+
+        (
+            pda::position(&user, mint),
+            Position {
+                user,
+                collateral_amount,
+                debt_amount,
+                prev_cumulative_interest_rate: INTEREST_SCALE,
+                redistributed_collateral_snapshot: 0,
+                redistributed_debt_snapshot: 0,
+                prev_position: None,
+                next_position: None,
+            },
+        )
+    }
+
+    #[test]
+    fn candidates_are_ordered_by_shortfall() {
+        let mint = Pubkey::new_unique();
+        let markets = HashMap::from([(mint, market(mint))]);
+
+        let small = position(&mint, 100_000_000, 90_000_000);
+        let healthy = position(&mint, 100_000_000, 80_000_000);
+        let large = position(&mint, 1_000_000_000, 900_000_000);
+        let medium = position(&mint, 100_000_000, 120_000_000);
+
+        let candidates = rank_candidates(
+            vec![small.clone(), healthy, large.clone(), medium.clone()],
+            &protocol_config(),
+            &markets,
+        )
+        .unwrap();
+
+        let users: Vec<Pubkey> = candidates.iter().map(|candidate| candidate.user).collect();
+        assert_eq!(users, vec![large.1.user, medium.1.user, small.1.user]);
+
+        let shortfalls: Vec<u128> = candidates
+            .iter()
+            .map(|candidate| candidate.shortfall)
+            .collect();
+        assert_eq!(shortfalls, vec![50_000_000, 35_000_000, 5_000_000]);
+    }
+
+    #[test]
+    fn positions_of_unknown_collaterals_are_skipped() {
+        let mint = Pubkey::new_unique();
+        let markets = HashMap::from([(mint, market(mint))]);
+
+        let unlisted = position(&Pubkey::new_unique(), 100_000_000, 120_000_000);
+
+        let candidates = rank_candidates(vec![unlisted], &protocol_config(), &markets).unwrap();
+
+        assert!(candidates.is_empty());
+    }
+}
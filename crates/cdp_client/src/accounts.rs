@@ -0,0 +1,18 @@
+use anchor_lang::{prelude::*, Discriminator};
+
+pub use cdp_stablecoin_protocol::state::{
+    Auction, CollateralConfig, Position, ProtocolConfig, StakeAccount,
+};
+
+#[cfg(feature = "mock-oracle")]
+pub use cdp_stablecoin_protocol::state::MockPrice;
+
+// Decode raw account data fetched over RPC, checking the discriminator
+pub fn decode<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..])
+}
+
+// First eight bytes of every account of type T, for getProgramAccounts memcmp filters
+pub fn discriminator<T: Discriminator>() -> [u8; 8] {
+    T::DISCRIMINATOR
+}
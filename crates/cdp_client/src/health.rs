@@ -0,0 +1,209 @@
+use anchor_lang::prelude::*;
+use cdp_stablecoin_protocol::{
+    constants::BPS_SCALE,
+    errors::ArithmeticError,
+    oracle::OraclePrice,
+    price_math,
+    state::{CollateralConfig, Position, ProtocolConfig},
+};
+
+pub struct PositionHealth {
+    pub collateral_amount: u64,
+    pub debt_amount: u64,
+    // In stablecoin base units
+    pub collateral_value: u128,
+    // In bps, u128::MAX when the collateral is worthless
+    pub ltv: u128,
+}
+
+impl PositionHealth {
+    pub fn is_liquidatable(&self, collateral_config: &CollateralConfig) -> bool {
+        self.debt_amount > 0 && self.ltv > collateral_config.liquidation_threshold as u128
+    }
+
+    // Debt above what the collateral supports at the liquidation threshold, in stablecoin
+    // base units
+    pub fn shortfall(&self, collateral_config: &CollateralConfig) -> u128 {
+        let supported_debt = self
+            .collateral_value
+            .saturating_mul(collateral_config.liquidation_threshold as u128)
+            / BPS_SCALE as u128;
+
+        (self.debt_amount as u128).saturating_sub(supported_debt)
+    }
+}
+
+// Position as the program sees it after settling accrued interest and pending
+// redistribution, which every instruction does before checking it
+pub fn settle_position(
+    position: &Position,
+    collateral_config: &CollateralConfig,
+    protocol_config: &ProtocolConfig,
+) -> Result<Position> {
+    let mut position = position.clone();
+    let mut collateral_config = collateral_config.clone();
+
+    collateral_config.apply_pending_redistribution(&mut position, protocol_config)?;
+
+    Ok(position)
+}
+
+// Health as liquidate_position and start_auction compute it, with the collateral valued
+// at the upper bound of the price. Pass the price from oracle::guarded_price, the one
+// these instructions use.
+pub fn position_health(
+    position: &Position,
+    collateral_config: &CollateralConfig,
+    protocol_config: &ProtocolConfig,
+    price: &OraclePrice,
+    collateral_decimals: u8,
+) -> Result<PositionHealth> {
+    let position = settle_position(position, collateral_config, protocol_config)?;
+
+    let collateral_value = price_math::collateral_value(
+        position.collateral_amount,
+        price.upper_bound(),
+        price.exponent,
+        collateral_decimals,
+    )?;
+
+    Ok(PositionHealth {
+        collateral_amount: position.collateral_amount,
+        debt_amount: position.debt_amount,
+        collateral_value,
+        ltv: price_math::ltv(position.debt_amount, collateral_value)?,
+    })
+}
+
+// Settled position as liquidate_position leaves it when asked to repay debt_amount,
+// before anything is redistributed back to it
+pub fn liquidated_position(
+    position: &Position,
+    collateral_config: &CollateralConfig,
+    price: &OraclePrice,
+    collateral_decimals: u8,
+    debt_amount: u64,
+) -> Result<Position> {
+    let mut position = position.clone();
+
+    let collateral_value = price_math::collateral_value(
+        position.collateral_amount,
+        price.upper_bound(),
+        price.exponent,
+        collateral_decimals,
+    )?;
+
+    let penalty_scale = BPS_SCALE as u128 + collateral_config.liquidation_penalty as u128;
+
+    let is_underwater = collateral_value.saturating_mul(BPS_SCALE as u128)
+        <= (position.debt_amount as u128).saturating_mul(penalty_scale);
+
+    let max_repay = if is_underwater {
+        position.debt_amount
+    } else {
+        ((position.debt_amount as u128) * collateral_config.close_factor as u128
+            / BPS_SCALE as u128) as u64
+    };
+
+    let repay_amount = match collateral_config
+        .cap_debt_reduction(position.debt_amount, debt_amount.min(max_repay))
+    {
+        0 => position.debt_amount,
+        repay_amount => repay_amount,
+    };
+
+    let collateral_seized = price_math::collateral_amount(
+        ((repay_amount as u128) * penalty_scale / BPS_SCALE as u128) as u64,
+        price.reference_price(),
+        price.exponent,
+        collateral_decimals,
+    )?
+    .min(position.collateral_amount as u128) as u64;
+
+    position.debt_amount -= repay_amount;
+    position.collateral_amount -= collateral_seized;
+
+    Ok(position)
+}
+
+// LTV open_position and adjust_position check against max_ltv, with the mint fee added to
+// the debt and the collateral valued at the lower bound of the price
+pub fn borrow_ltv(
+    collateral_amount: u64,
+    debt_amount: u64,
+    protocol_config: &ProtocolConfig,
+    price: &OraclePrice,
+    collateral_decimals: u8,
+) -> Result<u128> {
+    let total_debt_amount = debt_amount
+        .checked_add(protocol_config.calculate_mint_fee(debt_amount)?)
+        .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+    let collateral_value = price_math::collateral_value(
+        collateral_amount,
+        price.lower_bound(),
+        price.exponent,
+        collateral_decimals,
+    )?;
+
+    price_math::ltv(total_debt_amount, collateral_value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collateral_config(liquidation_threshold: u16) -> CollateralConfig {
+        // Every other field zeroed, shortfall only reads the threshold
+        let mut collateral_config =
+            CollateralConfig::deserialize(&mut &[0; CollateralConfig::INIT_SPACE][..]).unwrap();
+        collateral_config.liquidation_threshold = liquidation_threshold;
+        collateral_config
+    }
+
+    fn health(debt_amount: u64, collateral_value: u128) -> PositionHealth {
+        PositionHealth {
+            collateral_amount: 1_000_000,
+            debt_amount,
+            collateral_value,
+            ltv: price_math::ltv(debt_amount, collateral_value).unwrap(),
+        }
+    }
+
+    #[test]
+    fn shortfall_is_debt_above_the_threshold() {
+        let collateral_config = collateral_config(8_500);
+
+        // $100 of collateral supports $85 of debt
+        assert_eq!(
+            health(90_000_000, 100_000_000).shortfall(&collateral_config),
+            5_000_000
+        );
+        assert_eq!(
+            health(85_000_000, 100_000_000).shortfall(&collateral_config),
+            0
+        );
+        assert_eq!(
+            health(50_000_000, 100_000_000).shortfall(&collateral_config),
+            0
+        );
+    }
+
+    #[test]
+    fn worthless_collateral_leaves_the_whole_debt_short() {
+        let collateral_config = collateral_config(8_500);
+        let health = health(10_000_000, 0);
+
+        assert!(health.is_liquidatable(&collateral_config));
+        assert_eq!(health.shortfall(&collateral_config), 10_000_000);
+    }
+
+    #[test]
+    fn positions_without_debt_are_not_liquidatable() {
+        let collateral_config = collateral_config(8_500);
+
+        assert!(!health(0, 0).is_liquidatable(&collateral_config));
+        assert!(health(90_000_000, 100_000_000).is_liquidatable(&collateral_config));
+        assert!(!health(85_000_000, 100_000_000).is_liquidatable(&collateral_config));
+    }
+}
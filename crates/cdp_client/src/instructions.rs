@@ -0,0 +1,646 @@
+use anchor_lang::{
+    prelude::*, solana_program::instruction::Instruction, system_program, InstructionData,
+};
+use anchor_spl::{associated_token, associated_token::get_associated_token_address, token};
+use cdp_stablecoin_protocol::{
+    accounts, instruction,
+    state::{Auction, AuctionParams, LiquidationMode, OracleConfig, PauseFlags, SecondaryOracle},
+    ID,
+};
+
+use crate::pda;
+
+// One builder per handler in the program, every PDA and token account is derived from the
+// keys passed in
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+// Positions the program's position list needs, passed as remaining accounts
+fn with_positions(mut ix: Instruction, positions: &[Pubkey]) -> Instruction {
+    ix.accounts.extend(
+        positions
+            .iter()
+            .map(|position| AccountMeta::new(*position, false)),
+    );
+
+    ix
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn initialize_protocol_config(
+    admin: &Pubkey,
+    price_feed: &Pubkey,
+    protocol_fee: u16,
+    redemption_fee: u16,
+    mint_fee: u16,
+    base_rate: u16,
+    sigma: u16,
+    debt_ceiling: u64,
+    stablecoin_price_feed: String,
+    stablecoin_oracle: OracleConfig,
+) -> Instruction {
+    build(
+        accounts::InitializeProtocolConfig {
+            admin: *admin,
+            program: ID,
+            program_data: pda::program_data(),
+            protocol_config: pda::protocol_config(),
+            stable_mint: pda::stable_mint(),
+            auth: pda::auth(),
+            treasury_vault: pda::treasury_vault(),
+            interest_rewards_vault: pda::interest_rewards_vault(),
+            price_feed: *price_feed,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeProtocolConfig {
+            protocol_fee,
+            redemption_fee,
+            mint_fee,
+            base_rate,
+            sigma,
+            debt_ceiling,
+            stablecoin_price_feed,
+            stablecoin_oracle,
+        },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn initialize_collateral_vault(
+    admin: &Pubkey,
+    collateral_mint: &Pubkey,
+    price_feed: &Pubkey,
+    secondary_price_feed: Option<Pubkey>,
+    collateral_price_feed: String,
+    collateral_oracle: OracleConfig,
+    secondary_oracle: Option<SecondaryOracle>,
+    max_ltv: u16,
+    liquidation_threshold: u16,
+    liquidation_penalty: u16,
+    close_factor: u16,
+    liquidator_reward: u16,
+    liquidation_mode: LiquidationMode,
+    auction_params: AuctionParams,
+    debt_ceiling: u64,
+    min_debt: u64,
+) -> Instruction {
+    build(
+        accounts::InitializeCollateralVault {
+            admin: *admin,
+            collateral_mint: *collateral_mint,
+            collateral_vault_config: pda::collateral_vault_config(collateral_mint),
+            protocol_config: pda::protocol_config(),
+            auth: pda::auth(),
+            collateral_vault: pda::collateral_vault(collateral_mint),
+            liquidation_rewards_vault: pda::liquidation_rewards_vault(collateral_mint),
+            stable_mint: pda::stable_mint(),
+            stake_vault: pda::stake_vault(collateral_mint),
+            price_feed: *price_feed,
+            secondary_price_feed,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeCollateralVault {
+            collateral_price_feed,
+            collateral_oracle,
+            secondary_oracle,
+            max_ltv,
+            liquidation_threshold,
+            liquidation_penalty,
+            close_factor,
+            liquidator_reward,
+            liquidation_mode,
+            auction_params,
+            debt_ceiling,
+            min_debt,
+        },
+    )
+}
+
+pub fn open_position(
+    user: &Pubkey,
+    collateral_mint: &Pubkey,
+    price_feed: &Pubkey,
+    secondary_price_feed: Option<Pubkey>,
+    collateral_amount: u64,
+    debt_amount: u64,
+    positions: &[Pubkey],
+) -> Instruction {
+    with_positions(
+        build(
+            accounts::OpenPosition {
+                user: *user,
+                collateral_mint: *collateral_mint,
+                stable_mint: pda::stable_mint(),
+                protocol_config: pda::protocol_config(),
+                auth: pda::auth(),
+                user_ata: get_associated_token_address(user, collateral_mint),
+                user_stable_ata: get_associated_token_address(user, &pda::stable_mint()),
+                collateral_vault_config: pda::collateral_vault_config(collateral_mint),
+                position: pda::position(user, collateral_mint),
+                price_feed: *price_feed,
+                secondary_price_feed,
+                collateral_vault: pda::collateral_vault(collateral_mint),
+                treasury_vault: pda::treasury_vault(),
+                token_program: token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::OpenPosition {
+                collateral_amount,
+                debt_amount,
+            },
+        ),
+        positions,
+    )
+}
+
+pub fn close_position(
+    user: &Pubkey,
+    collateral_mint: &Pubkey,
+    price_feed: &Pubkey,
+    secondary_price_feed: Option<Pubkey>,
+    positions: &[Pubkey],
+) -> Instruction {
+    with_positions(
+        build(
+            accounts::ClosePosition {
+                user: *user,
+                collateral_mint: *collateral_mint,
+                stable_mint: pda::stable_mint(),
+                protocol_config: pda::protocol_config(),
+                auth: pda::auth(),
+                user_ata: get_associated_token_address(user, collateral_mint),
+                user_stable_ata: get_associated_token_address(user, &pda::stable_mint()),
+                collateral_vault_config: pda::collateral_vault_config(collateral_mint),
+                position: pda::position(user, collateral_mint),
+                price_feed: *price_feed,
+                secondary_price_feed,
+                collateral_vault: pda::collateral_vault(collateral_mint),
+                token_program: token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::ClosePosition {},
+        ),
+        positions,
+    )
+}
+
+pub fn adjust_position(
+    user: &Pubkey,
+    collateral_mint: &Pubkey,
+    price_feed: &Pubkey,
+    secondary_price_feed: Option<Pubkey>,
+    collateral_delta: i64,
+    debt_delta: i64,
+    positions: &[Pubkey],
+) -> Instruction {
+    with_positions(
+        build(
+            accounts::AdjustPosition {
+                user: *user,
+                collateral_mint: *collateral_mint,
+                stable_mint: pda::stable_mint(),
+                protocol_config: pda::protocol_config(),
+                auth: pda::auth(),
+                user_ata: get_associated_token_address(user, collateral_mint),
+                user_stable_ata: get_associated_token_address(user, &pda::stable_mint()),
+                collateral_vault_config: pda::collateral_vault_config(collateral_mint),
+                position: pda::position(user, collateral_mint),
+                price_feed: *price_feed,
+                secondary_price_feed,
+                collateral_vault: pda::collateral_vault(collateral_mint),
+                treasury_vault: pda::treasury_vault(),
+                token_program: token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::AdjustPosition {
+                collateral_delta,
+                debt_delta,
+            },
+        ),
+        positions,
+    )
+}
+
+pub fn update_interest_rate(user: &Pubkey, price_feed: &Pubkey) -> Instruction {
+    build(
+        accounts::UpdateInterestRate {
+            user: *user,
+            protocol_config: pda::protocol_config(),
+            price_feed: *price_feed,
+        },
+        instruction::UpdateInterestRate {},
+    )
+}
+
+pub fn stake_stable_tokens(user: &Pubkey, collateral_mint: &Pubkey, amount: u64) -> Instruction {
+    build(
+        accounts::Stake {
+            user: *user,
+            stake_account: pda::stake_account(user, collateral_mint),
+            stable_mint: pda::stable_mint(),
+            user_stable_ata: get_associated_token_address(user, &pda::stable_mint()),
+            auth: pda::auth(),
+            stake_vault: pda::stake_vault(collateral_mint),
+            collateral_vault_config: pda::collateral_vault_config(collateral_mint),
+            protocol_config: pda::protocol_config(),
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        instruction::StakeStableTokens { amount },
+    )
+}
+
+pub fn unstake_stable_tokens(user: &Pubkey, collateral_mint: &Pubkey) -> Instruction {
+    build(
+        accounts::UnStake {
+            user: *user,
+            stake_account: pda::stake_account(user, collateral_mint),
+            stable_mint: pda::stable_mint(),
+            user_stable_ata: get_associated_token_address(user, &pda::stable_mint()),
+            auth: pda::auth(),
+            stake_vault: pda::stake_vault(collateral_mint),
+            collateral_vault_config: pda::collateral_vault_config(collateral_mint),
+            protocol_config: pda::protocol_config(),
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        instruction::UnstakeStableTokens {},
+    )
+}
+
+pub fn liquidate_position(
+    liquidator: &Pubkey,
+    user: &Pubkey,
+    collateral_mint: &Pubkey,
+    price_feed: &Pubkey,
+    secondary_price_feed: Option<Pubkey>,
+    debt_amount: u64,
+    positions: &[Pubkey],
+) -> Instruction {
+    with_positions(
+        build(
+            accounts::LiquidatePosition {
+                liquidator: *liquidator,
+                user: *user,
+                collateral_mint: *collateral_mint,
+                stable_mint: pda::stable_mint(),
+                protocol_config: pda::protocol_config(),
+                auth: pda::auth(),
+                user_ata: get_associated_token_address(user, collateral_mint),
+                user_stable_ata: get_associated_token_address(user, &pda::stable_mint()),
+                liquidator_ata: get_associated_token_address(liquidator, collateral_mint),
+                collateral_vault_config: pda::collateral_vault_config(collateral_mint),
+                position: pda::position(user, collateral_mint),
+                price_feed: *price_feed,
+                secondary_price_feed,
+                collateral_vault: pda::collateral_vault(collateral_mint),
+                liquidation_rewards_vault: pda::liquidation_rewards_vault(collateral_mint),
+                stake_vault: pda::stake_vault(collateral_mint),
+                token_program: token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::LiquidatePosition { debt_amount },
+        ),
+        positions,
+    )
+}
+
+// auction_id is the collateral's auction_count when the auction is started
+pub fn start_auction(
+    keeper: &Pubkey,
+    user: &Pubkey,
+    collateral_mint: &Pubkey,
+    auction_id: u64,
+    price_feed: &Pubkey,
+    secondary_price_feed: Option<Pubkey>,
+    positions: &[Pubkey],
+) -> Instruction {
+    with_positions(
+        build(
+            accounts::StartAuction {
+                keeper: *keeper,
+                user: *user,
+                collateral_mint: *collateral_mint,
+                protocol_config: pda::protocol_config(),
+                collateral_vault_config: pda::collateral_vault_config(collateral_mint),
+                position: pda::position(user, collateral_mint),
+                auction: pda::auction(collateral_mint, auction_id),
+                price_feed: *price_feed,
+                secondary_price_feed,
+                system_program: system_program::ID,
+            },
+            instruction::StartAuction {},
+        ),
+        positions,
+    )
+}
+
+pub fn bid_auction(bidder: &Pubkey, auction: &Auction, collateral_amount: u64) -> Instruction {
+    let collateral_mint = &auction.collateral_mint;
+
+    build(
+        accounts::BidAuction {
+            bidder: *bidder,
+            owner: auction.owner,
+            keeper: auction.keeper,
+            collateral_mint: *collateral_mint,
+            stable_mint: pda::stable_mint(),
+            protocol_config: pda::protocol_config(),
+            auth: pda::auth(),
+            bidder_ata: get_associated_token_address(bidder, collateral_mint),
+            bidder_stable_ata: get_associated_token_address(bidder, &pda::stable_mint()),
+            owner_ata: get_associated_token_address(&auction.owner, collateral_mint),
+            collateral_vault_config: pda::collateral_vault_config(collateral_mint),
+            auction: pda::auction(collateral_mint, auction.id),
+            collateral_vault: pda::collateral_vault(collateral_mint),
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::BidAuction { collateral_amount },
+    )
+}
+
+pub fn reset_auction(
+    user: &Pubkey,
+    auction: &Auction,
+    price_feed: &Pubkey,
+    secondary_price_feed: Option<Pubkey>,
+) -> Instruction {
+    let collateral_mint = &auction.collateral_mint;
+
+    build(
+        accounts::ResetAuction {
+            user: *user,
+            collateral_mint: *collateral_mint,
+            protocol_config: pda::protocol_config(),
+            collateral_vault_config: pda::collateral_vault_config(collateral_mint),
+            auction: pda::auction(collateral_mint, auction.id),
+            price_feed: *price_feed,
+            secondary_price_feed,
+        },
+        instruction::ResetAuction {},
+    )
+}
+
+pub fn claim_stake_reward(user: &Pubkey, collateral_mint: &Pubkey) -> Instruction {
+    build(
+        accounts::ClaimStakeRewards {
+            user: *user,
+            collateral_mint: *collateral_mint,
+            stable_mint: pda::stable_mint(),
+            user_ata: get_associated_token_address(user, collateral_mint),
+            user_stable_ata: get_associated_token_address(user, &pda::stable_mint()),
+            protocol_config: pda::protocol_config(),
+            auth: pda::auth(),
+            collateral_vault_config: pda::collateral_vault_config(collateral_mint),
+            liquidation_rewards_vault: pda::liquidation_rewards_vault(collateral_mint),
+            interest_rewards_vault: pda::interest_rewards_vault(),
+            stake_account: pda::stake_account(user, collateral_mint),
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::ClaimStakeReward {},
+    )
+}
+
+pub fn collect_interest(user: &Pubkey) -> Instruction {
+    build(
+        accounts::CollectInterest {
+            user: *user,
+            stable_mint: pda::stable_mint(),
+            protocol_config: pda::protocol_config(),
+            auth: pda::auth(),
+            treasury_vault: pda::treasury_vault(),
+            interest_rewards_vault: pda::interest_rewards_vault(),
+            token_program: token::ID,
+        },
+        instruction::CollectInterest {},
+    )
+}
+
+// positions must be sorted riskiest first, they are passed as writable remaining accounts
+pub fn redeem(
+    redeemer: &Pubkey,
+    collateral_mint: &Pubkey,
+    price_feed: &Pubkey,
+    secondary_price_feed: Option<Pubkey>,
+    amount: u64,
+    positions: &[Pubkey],
+) -> Instruction {
+    with_positions(
+        build(
+            accounts::Redeem {
+                redeemer: *redeemer,
+                collateral_mint: *collateral_mint,
+                stable_mint: pda::stable_mint(),
+                protocol_config: pda::protocol_config(),
+                auth: pda::auth(),
+                redeemer_ata: get_associated_token_address(redeemer, collateral_mint),
+                redeemer_stable_ata: get_associated_token_address(redeemer, &pda::stable_mint()),
+                collateral_vault_config: pda::collateral_vault_config(collateral_mint),
+                price_feed: *price_feed,
+                secondary_price_feed,
+                collateral_vault: pda::collateral_vault(collateral_mint),
+                token_program: token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::Redeem { amount },
+        ),
+        positions,
+    )
+}
+
+pub fn propose_admin(admin: &Pubkey, new_admin: Pubkey) -> Instruction {
+    build(
+        accounts::ProposeAdmin {
+            admin: *admin,
+            protocol_config: pda::protocol_config(),
+        },
+        instruction::ProposeAdmin { new_admin },
+    )
+}
+
+pub fn accept_admin(pending_admin: &Pubkey) -> Instruction {
+    build(
+        accounts::AcceptAdmin {
+            pending_admin: *pending_admin,
+            protocol_config: pda::protocol_config(),
+        },
+        instruction::AcceptAdmin {},
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+// price_feed is the stablecoin price account, interest is settled before the rate
+// parameters change
+pub fn update_protocol_config(
+    admin: &Pubkey,
+    price_feed: &Pubkey,
+    protocol_fee: u16,
+    redemption_fee: u16,
+    mint_fee: u16,
+    base_rate: u16,
+    sigma: u16,
+    debt_ceiling: u64,
+) -> Instruction {
+    build(
+        accounts::UpdateProtocolConfig {
+            admin: *admin,
+            protocol_config: pda::protocol_config(),
+            price_feed: *price_feed,
+        },
+        instruction::UpdateProtocolConfig {
+            protocol_fee,
+            redemption_fee,
+            mint_fee,
+            base_rate,
+            sigma,
+            debt_ceiling,
+        },
+    )
+}
+
+pub fn set_collateral_debt_limits(
+    admin: &Pubkey,
+    collateral_mint: &Pubkey,
+    debt_ceiling: u64,
+    min_debt: u64,
+) -> Instruction {
+    build(
+        accounts::SetCollateralDebtLimits {
+            admin: *admin,
+            collateral_mint: *collateral_mint,
+            protocol_config: pda::protocol_config(),
+            collateral_vault_config: pda::collateral_vault_config(collateral_mint),
+        },
+        instruction::SetCollateralDebtLimits {
+            debt_ceiling,
+            min_debt,
+        },
+    )
+}
+
+pub fn set_guardian(admin: &Pubkey, guardian: Pubkey) -> Instruction {
+    build(
+        accounts::SetGuardian {
+            admin: *admin,
+            protocol_config: pda::protocol_config(),
+        },
+        instruction::SetGuardian { guardian },
+    )
+}
+
+pub fn set_protocol_pause(authority: &Pubkey, paused: PauseFlags) -> Instruction {
+    build(
+        accounts::SetProtocolPause {
+            authority: *authority,
+            protocol_config: pda::protocol_config(),
+        },
+        instruction::SetProtocolPause { paused },
+    )
+}
+
+pub fn set_collateral_pause(
+    authority: &Pubkey,
+    collateral_mint: &Pubkey,
+    paused: PauseFlags,
+) -> Instruction {
+    build(
+        accounts::SetCollateralPause {
+            authority: *authority,
+            collateral_mint: *collateral_mint,
+            protocol_config: pda::protocol_config(),
+            collateral_vault_config: pda::collateral_vault_config(collateral_mint),
+        },
+        instruction::SetCollateralPause { paused },
+    )
+}
+
+pub fn update_collateral_oracle(
+    admin: &Pubkey,
+    collateral_mint: &Pubkey,
+    price_feed: &Pubkey,
+    secondary_price_feed: Option<Pubkey>,
+    collateral_price_feed: String,
+    collateral_oracle: OracleConfig,
+    secondary_oracle: Option<SecondaryOracle>,
+) -> Instruction {
+    build(
+        accounts::UpdateCollateralOracle {
+            admin: *admin,
+            collateral_mint: *collateral_mint,
+            protocol_config: pda::protocol_config(),
+            collateral_vault_config: pda::collateral_vault_config(collateral_mint),
+            price_feed: *price_feed,
+            secondary_price_feed,
+        },
+        instruction::UpdateCollateralOracle {
+            collateral_price_feed,
+            collateral_oracle,
+            secondary_oracle,
+        },
+    )
+}
+
+pub fn update_stablecoin_oracle(
+    admin: &Pubkey,
+    price_feed: &Pubkey,
+    stablecoin_price_feed: String,
+    stablecoin_oracle: OracleConfig,
+) -> Instruction {
+    build(
+        accounts::UpdateStablecoinOracle {
+            admin: *admin,
+            protocol_config: pda::protocol_config(),
+            price_feed: *price_feed,
+        },
+        instruction::UpdateStablecoinOracle {
+            stablecoin_price_feed,
+            stablecoin_oracle,
+        },
+    )
+}
+
+// Only in mock-oracle builds of the program
+#[cfg(feature = "mock-oracle")]
+pub fn set_mock_price(
+    admin: &Pubkey,
+    feed_id: [u8; 32],
+    price: i64,
+    ema_price: i64,
+    conf: u64,
+    exponent: i32,
+) -> Instruction {
+    build(
+        accounts::SetMockPrice {
+            admin: *admin,
+            program: ID,
+            program_data: pda::program_data(),
+            mock_price: pda::mock_price(&feed_id),
+            system_program: system_program::ID,
+        },
+        instruction::SetMockPrice {
+            feed_id,
+            price,
+            ema_price,
+            conf,
+            exponent,
+        },
+    )
+}
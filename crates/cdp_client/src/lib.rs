@@ -0,0 +1,13 @@
+// Off-chain helpers for the CDP stablecoin program: PDA derivation, instruction builders,
+// account decoding, oracle reads, position health and transaction sending, all built on the
+// program crate itself
+pub mod accounts;
+pub mod health;
+pub mod instructions;
+pub mod oracle;
+pub mod pda;
+pub mod position_list;
+pub mod rpc;
+
+pub use cdp_stablecoin_protocol as program;
+pub use cdp_stablecoin_protocol::ID;
@@ -0,0 +1,172 @@
+use anchor_lang::prelude::*;
+use cdp_stablecoin_protocol::{
+    oracle::{self, OraclePrice},
+    state::{CollateralConfig, OracleConfig, OracleSource},
+};
+
+use crate::pda;
+
+// Raw price of feed_id in an account fetched over RPC, decoded by the program's own oracle
+// backends. Staleness and confidence are left to the caller since there is no clock here
+pub fn read_price(
+    key: &Pubkey,
+    owner: &Pubkey,
+    data: &[u8],
+    feed_id: &[u8; 32],
+    oracle_config: &OracleConfig,
+) -> Result<OraclePrice> {
+    let mut lamports = 0;
+    let mut data = data.to_vec();
+    let account_info =
+        AccountInfo::new(key, false, false, &mut lamports, &mut data, owner, false, 0);
+
+    oracle::load_price(&account_info, feed_id, oracle_config)
+}
+
+// Collateral price the program's get_guarded_price would use at current_timestamp, from
+// the raw primary and secondary prices. None when the program would treat the collateral
+// as frozen, so nothing priced off it can go through.
+pub fn guarded_price(
+    collateral_config: &CollateralConfig,
+    primary: OraclePrice,
+    secondary: Option<OraclePrice>,
+    current_timestamp: i64,
+) -> Result<Option<OraclePrice>> {
+    let usable = |price: OraclePrice, oracle_config: &OracleConfig| {
+        price
+            .validate_at(oracle_config, current_timestamp)
+            .is_ok()
+            .then_some(price)
+    };
+
+    let primary = usable(primary, &collateral_config.collateral_oracle);
+
+    let Some(secondary_oracle) = collateral_config.secondary_oracle else {
+        return Ok(primary);
+    };
+
+    oracle::guard_prices(
+        primary,
+        secondary.and_then(|secondary| usable(secondary, &secondary_oracle.oracle)),
+        secondary_oracle.max_deviation,
+    )
+}
+
+// Price account of a feed when it follows from the feed id. Pyth price update accounts
+// are chosen by whoever posts them, so they have to be passed explicitly
+pub fn price_feed_address(feed_id: &[u8; 32], oracle_config: &OracleConfig) -> Option<Pubkey> {
+    match oracle_config.source {
+        OracleSource::Pyth => None,
+        OracleSource::Switchboard => Some(Pubkey::new_from_array(*feed_id)),
+        OracleSource::Mock => Some(pda::mock_price(feed_id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cdp_stablecoin_protocol::state::{PriceMode, SecondaryOracle};
+
+    use super::*;
+
+    fn zeroed<T: AnchorDeserialize + Space>() -> T {
+        T::deserialize(&mut &vec![0; T::INIT_SPACE][..]).unwrap()
+    }
+
+    fn oracle_config() -> OracleConfig {
+        OracleConfig {
+            source: OracleSource::Mock,
+            price_mode: PriceMode::Spot,
+            max_price_age: 60,
+            max_confidence: 100,
+        }
+    }
+
+    fn price(price: i64, conf: u64, publish_time: i64) -> OraclePrice {
+        OraclePrice {
+            price,
+            conf,
+            ema_price: price,
+            ema_conf: conf,
+            exponent: -8,
+            publish_time,
+            mode: PriceMode::Spot,
+        }
+    }
+
+    fn collateral_config(secondary_oracle: Option<SecondaryOracle>) -> CollateralConfig {
+        let mut collateral_config: CollateralConfig = zeroed();
+        collateral_config.collateral_oracle = oracle_config();
+        collateral_config.secondary_oracle = secondary_oracle;
+        collateral_config
+    }
+
+    fn guarded(
+        collateral_config: &CollateralConfig,
+        primary: OraclePrice,
+        secondary: Option<OraclePrice>,
+    ) -> Option<i64> {
+        guarded_price(collateral_config, primary, secondary, 1_000)
+            .unwrap()
+            .map(|price| price.price)
+    }
+
+    #[test]
+    fn stale_or_uncertain_primary_prices_freeze_the_collateral() {
+        let collateral_config = collateral_config(None);
+
+        assert_eq!(
+            guarded(&collateral_config, price(200_00000000, 0, 990), None),
+            Some(200_00000000)
+        );
+        assert_eq!(
+            guarded(&collateral_config, price(200_00000000, 0, 900), None),
+            None
+        );
+        assert_eq!(
+            guarded(
+                &collateral_config,
+                price(200_00000000, 3_00000000, 990),
+                None
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn the_secondary_cross_checks_and_stands_in_for_the_primary() {
+        let collateral_config = collateral_config(Some(SecondaryOracle {
+            price_feed: [1; 32],
+            oracle: oracle_config(),
+            max_deviation: 100,
+        }));
+
+        assert_eq!(
+            guarded(
+                &collateral_config,
+                price(200_00000000, 0, 990),
+                Some(price(201_00000000, 0, 990))
+            ),
+            Some(200_00000000)
+        );
+        assert_eq!(
+            guarded(
+                &collateral_config,
+                price(200_00000000, 0, 990),
+                Some(price(210_00000000, 0, 990))
+            ),
+            None
+        );
+        assert_eq!(
+            guarded(
+                &collateral_config,
+                price(200_00000000, 0, 900),
+                Some(price(210_00000000, 0, 990))
+            ),
+            Some(210_00000000)
+        );
+        assert_eq!(
+            guarded(&collateral_config, price(200_00000000, 0, 990), None),
+            None
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use anchor_lang::{prelude::Pubkey, solana_program::bpf_loader_upgradeable};
+use cdp_stablecoin_protocol::ID;
+
+fn find(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &ID).0
+}
+
+pub fn protocol_config() -> Pubkey {
+    find(&[b"config"])
+}
+
+pub fn auth() -> Pubkey {
+    find(&[b"auth"])
+}
+
+pub fn stable_mint() -> Pubkey {
+    find(&[b"stable"])
+}
+
+pub fn treasury_vault() -> Pubkey {
+    find(&[b"treasury", stable_mint().as_ref()])
+}
+
+pub fn interest_rewards_vault() -> Pubkey {
+    find(&[b"interest_rewards_vault", stable_mint().as_ref()])
+}
+
+pub fn collateral_vault_config(collateral_mint: &Pubkey) -> Pubkey {
+    find(&[b"collateral", collateral_mint.as_ref()])
+}
+
+pub fn collateral_vault(collateral_mint: &Pubkey) -> Pubkey {
+    find(&[b"collateral_vault", collateral_mint.as_ref()])
+}
+
+pub fn liquidation_rewards_vault(collateral_mint: &Pubkey) -> Pubkey {
+    find(&[b"liquidation_rewards_vault", collateral_mint.as_ref()])
+}
+
+pub fn stake_vault(collateral_mint: &Pubkey) -> Pubkey {
+    find(&[
+        b"stake_vault",
+        stable_mint().as_ref(),
+        collateral_mint.as_ref(),
+    ])
+}
+
+pub fn position(user: &Pubkey, collateral_mint: &Pubkey) -> Pubkey {
+    find(&[b"position", user.as_ref(), collateral_mint.as_ref()])
+}
+
+pub fn stake_account(user: &Pubkey, collateral_mint: &Pubkey) -> Pubkey {
+    find(&[b"stake", user.as_ref(), collateral_mint.as_ref()])
+}
+
+pub fn auction(collateral_mint: &Pubkey, auction_id: u64) -> Pubkey {
+    find(&[
+        b"auction",
+        collateral_mint.as_ref(),
+        auction_id.to_le_bytes().as_ref(),
+    ])
+}
+
+pub fn mock_price(feed_id: &[u8; 32]) -> Pubkey {
+    find(&[b"mock_price", feed_id.as_ref()])
+}
+
+// Upgrade metadata of the program, initialize_protocol_config checks its upgrade authority
+pub fn program_data() -> Pubkey {
+    Pubkey::find_program_address(&[ID.as_ref()], &bpf_loader_upgradeable::id()).0
+}
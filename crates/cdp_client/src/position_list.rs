@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+
+use anchor_lang::prelude::*;
+use cdp_stablecoin_protocol::{
+    errors::{ArithmeticError, RedemptionError},
+    oracle::OraclePrice,
+    price_math,
+    state::{CollateralConfig, Position, ProtocolConfig},
+};
+
+use crate::health;
+
+// Remaining accounts for instructions that move positions in a collateral's risk ordered
+// list, worked out from every position of that collateral. One extra position is passed on
+// either side of a new place, so the transaction still finds it when positions nearby
+// move before it lands.
+
+// Positions in the collateral's list, riskiest first
+pub fn listed(
+    positions: &[(Pubkey, Position)],
+    collateral_config: &CollateralConfig,
+) -> Vec<(Pubkey, Position)> {
+    let by_key: HashMap<Pubkey, &Position> = positions
+        .iter()
+        .map(|(key, position)| (*key, position))
+        .collect();
+
+    let mut listed = Vec::new();
+    let mut cursor = collateral_config.riskiest_position;
+
+    while let Some(key) = cursor {
+        let Some(position) = by_key.get(&key) else {
+            break;
+        };
+        if listed.len() == positions.len() {
+            break;
+        }
+
+        listed.push((key, (*position).clone()));
+        cursor = position.next_position;
+    }
+
+    listed
+}
+
+// Accounts to move the position at `key` to where `moved`, its state after the
+// instruction, ranks. Only its current neighbours are needed when `moved` carries no debt
+// or the position is closed.
+pub fn move_accounts(
+    positions: &[(Pubkey, Position)],
+    collateral_config: &CollateralConfig,
+    key: &Pubkey,
+    moved: Option<&Position>,
+) -> Result<Vec<Pubkey>> {
+    let listed = listed(positions, collateral_config);
+    let mut accounts = Vec::new();
+
+    if let Some(index) = listed.iter().position(|(listed_key, _)| listed_key == key) {
+        if index > 0 {
+            push_unique(&mut accounts, listed[index - 1].0);
+        }
+        if let Some((next, _)) = listed.get(index + 1) {
+            push_unique(&mut accounts, *next);
+        }
+    }
+
+    if let Some(moved) = moved.filter(|moved| moved.debt_amount > 0) {
+        let others: Vec<(Pubkey, Position)> = listed
+            .into_iter()
+            .filter(|(listed_key, _)| listed_key != key)
+            .collect();
+
+        let index = place(&others, collateral_config, moved)?;
+        push_around(&mut accounts, &others, index);
+    }
+
+    Ok(accounts)
+}
+
+// Accounts redeem needs to take `amount` of debt from the riskiest positions: the
+// positions it redeems, in list order, the one after them and the neighbours of the place
+// a partly redeemed last position moves to
+pub fn redemption_accounts(
+    positions: &[(Pubkey, Position)],
+    collateral_config: &CollateralConfig,
+    protocol_config: &ProtocolConfig,
+    amount: u64,
+    price: &OraclePrice,
+    collateral_decimals: u8,
+) -> Result<Vec<Pubkey>> {
+    let listed = listed(positions, collateral_config);
+    let mut accounts = Vec::new();
+    let mut remainder = amount;
+
+    for (index, (key, position)) in listed.iter().enumerate() {
+        let mut position = health::settle_position(position, collateral_config, protocol_config)?;
+        accounts.push(*key);
+
+        if position.debt_amount < remainder {
+            remainder -= position.debt_amount;
+            continue;
+        }
+
+        let rest = &listed[index + 1..];
+
+        if let Some((next, _)) = rest.first() {
+            push_unique(&mut accounts, *next);
+        }
+
+        if position.debt_amount > remainder {
+            position.collateral_amount =
+                position
+                    .collateral_amount
+                    .saturating_sub(price_math::redeemed_collateral(
+                        remainder,
+                        price.upper_bound(),
+                        price.exponent,
+                        collateral_decimals,
+                        protocol_config.redemption_fee,
+                    )?);
+            position.debt_amount -= remainder;
+
+            let index = place(rest, collateral_config, &position)?;
+            push_around(&mut accounts, rest, index);
+        }
+
+        return Ok(accounts);
+    }
+
+    err!(RedemptionError::InsufficientDebtToRedeem)
+}
+
+// Index of the first listed position that `position` is at least as risky as
+fn place(
+    listed: &[(Pubkey, Position)],
+    collateral_config: &CollateralConfig,
+    position: &Position,
+) -> Result<usize> {
+    let (debt, collateral) = collateral_config.position_risk(position)?;
+
+    for (index, (_, other)) in listed.iter().enumerate() {
+        let (other_debt, other_collateral) = collateral_config.position_risk(other)?;
+
+        if debt
+            .checked_mul(other_collateral)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            >= other_debt
+                .checked_mul(collateral)
+                .ok_or(ArithmeticError::ArithmeticOverflow)?
+        {
+            return Ok(index);
+        }
+    }
+
+    Ok(listed.len())
+}
+
+// The positions either side of `index` and one more beyond each
+fn push_around(accounts: &mut Vec<Pubkey>, listed: &[(Pubkey, Position)], index: usize) {
+    for (key, _) in &listed[index.saturating_sub(2)..(index + 2).min(listed.len())] {
+        push_unique(accounts, *key);
+    }
+}
+
+fn push_unique(accounts: &mut Vec<Pubkey>, key: Pubkey) {
+    if !accounts.contains(&key) {
+        accounts.push(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cdp_stablecoin_protocol::{constants::INTEREST_SCALE, state::PriceMode};
+
+    use super::*;
+
+    fn zeroed<T: AnchorDeserialize + Space>() -> T {
+        T::deserialize(&mut &vec![0; T::INIT_SPACE][..]).unwrap()
+    }
+
+    // Listed positions of 1 token each with the given debts, linked in the order given
+    fn list(debts: &[u64]) -> (Vec<(Pubkey, Position)>, CollateralConfig) {
+        let mut collateral_config: CollateralConfig = zeroed();
+        let keys: Vec<Pubkey> = debts.iter().map(|_| Pubkey::new_unique()).collect();
+
+        let positions = debts
+            .iter()
+            .enumerate()
+            .map(|(index, debt)| {
+                let mut position: Position = zeroed();
+                position.collateral_amount = 1_000_000;
+                position.debt_amount = *debt;
+                position.prev_cumulative_interest_rate = INTEREST_SCALE;
+                position.prev_position = index.checked_sub(1).map(|prev| keys[prev]);
+                position.next_position = keys.get(index + 1).copied();
+                (keys[index], position)
+            })
+            .collect();
+
+        collateral_config.riskiest_position = keys.first().copied();
+
+        (positions, collateral_config)
+    }
+
+    #[test]
+    fn moving_passes_the_old_and_new_neighbours() {
+        let (positions, collateral_config) = list(&[
+            90_000_000, 80_000_000, 70_000_000, 60_000_000, 50_000_000, 40_000_000,
+        ]);
+        let keys: Vec<Pubkey> = positions.iter().map(|(key, _)| *key).collect();
+
+        // Repaying the second position down below the fifth
+        let mut moved = positions[1].1.clone();
+        moved.debt_amount = 45_000_000;
+
+        let accounts =
+            move_accounts(&positions, &collateral_config, &keys[1], Some(&moved)).unwrap();
+        assert_eq!(accounts, vec![keys[0], keys[2], keys[3], keys[4], keys[5]]);
+
+        // Closing it only needs its neighbours
+        let accounts = move_accounts(&positions, &collateral_config, &keys[1], None).unwrap();
+        assert_eq!(accounts, vec![keys[0], keys[2]]);
+
+        // A new riskiest position goes first
+        let mut opened = positions[0].1.clone();
+        opened.debt_amount = 95_000_000;
+        let accounts = move_accounts(
+            &positions,
+            &collateral_config,
+            &Pubkey::new_unique(),
+            Some(&opened),
+        )
+        .unwrap();
+        assert_eq!(accounts, vec![keys[0], keys[1]]);
+    }
+
+    #[test]
+    fn redemption_walks_from_the_riskiest_position() {
+        let (positions, collateral_config) =
+            list(&[90_000_000, 80_000_000, 70_000_000, 60_000_000, 50_000_000]);
+        let keys: Vec<Pubkey> = positions.iter().map(|(key, _)| *key).collect();
+
+        let mut protocol_config: ProtocolConfig = zeroed();
+        protocol_config.cumulative_interest_rate = INTEREST_SCALE;
+
+        // $200 per token
+        let price = OraclePrice {
+            price: 200_00000000,
+            conf: 0,
+            ema_price: 200_00000000,
+            ema_conf: 0,
+            exponent: -8,
+            publish_time: 0,
+            mode: PriceMode::Spot,
+        };
+
+        // The first position in full and most of the second, which becomes the safest
+        let accounts = redemption_accounts(
+            &positions,
+            &collateral_config,
+            &protocol_config,
+            160_000_000,
+            &price,
+            6,
+        )
+        .unwrap();
+        assert_eq!(accounts, vec![keys[0], keys[1], keys[2], keys[3], keys[4]]);
+
+        // Exactly the first position, its next one becomes the head
+        let accounts = redemption_accounts(
+            &positions,
+            &collateral_config,
+            &protocol_config,
+            90_000_000,
+            &price,
+            6,
+        )
+        .unwrap();
+        assert_eq!(accounts, vec![keys[0], keys[1]]);
+
+        assert!(redemption_accounts(
+            &positions,
+            &collateral_config,
+            &protocol_config,
+            400_000_000,
+            &price,
+            6,
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,99 @@
+use anchor_lang::{prelude::Pubkey, AccountDeserialize, Discriminator};
+use anyhow::anyhow;
+use cdp_stablecoin_protocol::{state::Position, ID};
+use solana_account_decoder::UiAccountEncoding;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::{
+    config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{
+    instruction::Instruction,
+    signature::{read_keypair_file, Keypair, Signature},
+    signer::Signer,
+    transaction::Transaction,
+};
+
+use crate::{accounts, pda};
+
+// Keypair paths may start with ~/ like in the Solana CLI config
+fn expand_home(path: &str) -> anyhow::Result<String> {
+    Ok(match path.strip_prefix("~/") {
+        Some(path) => format!("{}/{}", std::env::var("HOME")?, path),
+        None => path.to_string(),
+    })
+}
+
+pub fn read_keypair(path: &str) -> anyhow::Result<Keypair> {
+    let path = expand_home(path)?;
+
+    read_keypair_file(&path).map_err(|error| anyhow!("cannot read keypair {}: {}", path, error))
+}
+
+// Sign a single instruction with the payer and wait for confirmation
+pub fn send_instruction(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    instruction: Instruction,
+) -> anyhow::Result<Signature> {
+    let blockhash = rpc.get_latest_blockhash()?;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[payer],
+        blockhash,
+    );
+
+    Ok(rpc.send_and_confirm_transaction(&transaction)?)
+}
+
+// Every account of type T owned by the program, found by its discriminator
+pub fn program_accounts<T: AccountDeserialize + Discriminator>(
+    rpc: &RpcClient,
+) -> anyhow::Result<Vec<(Pubkey, T)>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            0,
+            accounts::discriminator::<T>().to_vec(),
+        ))]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+
+    rpc.get_program_accounts_with_config(&ID, config)?
+        .into_iter()
+        .map(|(key, account)| Ok((key, accounts::decode(&account.data)?)))
+        .collect()
+}
+
+// Positions of one collateral, which position_list works out remaining accounts from.
+// Positions do not store their mint, it is recovered from the PDA seeds.
+pub fn collateral_positions(
+    rpc: &RpcClient,
+    collateral_mint: &Pubkey,
+) -> anyhow::Result<Vec<(Pubkey, Position)>> {
+    Ok(program_accounts::<Position>(rpc)?
+        .into_iter()
+        .filter(|(key, position)| pda::position(&position.user, collateral_mint) == *key)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_home_only_touches_a_leading_tilde() {
+        let home = std::env::var("HOME").unwrap();
+
+        assert_eq!(
+            expand_home("~/.config/solana/id.json").unwrap(),
+            format!("{}/.config/solana/id.json", home)
+        );
+        assert_eq!(expand_home("/tmp/id.json").unwrap(), "/tmp/id.json");
+        assert_eq!(expand_home("keys/~/id.json").unwrap(), "keys/~/id.json");
+    }
+}
@@ -4,13 +4,87 @@ use anchor_lang::error_code;
 pub enum PositionError {
     #[msg("Interest Rate Should Be Between 1% And 100%")]
     InvalidInterestRate,
-    #[msg("LTV Should Be Between 1% And 80%")]
+    #[msg("LTV Is Outside The Limits Of This Collateral")]
     InvalidLTV,
+    #[msg("Position Adjustment Is Invalid")]
+    InvalidAdjustment,
+    #[msg("Minting Would Exceed The Debt Ceiling Of This Collateral")]
+    CollateralDebtCeilingExceeded,
+    #[msg("Minting Would Exceed The Global Debt Ceiling")]
+    GlobalDebtCeilingExceeded,
+    #[msg("Position Debt Is Below The Minimum")]
+    DebtBelowMinimum,
+    #[msg("Account Is Not A Writable Position Of This Collateral")]
+    InvalidListPosition,
+    #[msg("Neighbours Of The Position In The Risk Ordered List Were Not Passed")]
+    MissingListNeighbours,
 }
 #[error_code]
 pub enum CollateralError {
     #[msg("This Mint Is Not Supported By The Protocol")]
     InvalidMintAsCollateral,
+    #[msg("Max LTV Must Be Below The Liquidation Threshold And Leave Room For The Penalty")]
+    InvalidRiskParameters,
+    #[msg("Auction Must Decay From Its Start Price To A Lower Non Zero Floor")]
+    InvalidAuctionParameters,
+}
+
+#[error_code]
+pub enum OracleError {
+    #[msg("Oracle Price Is Older Than The Allowed Age")]
+    StalePrice,
+    #[msg("Oracle Confidence Interval Is Too Wide")]
+    PriceConfidenceTooWide,
+    #[msg("Oracle Price Must Be Positive")]
+    InvalidPrice,
+    #[msg("Oracle Max Age And Confidence Must Be Set")]
+    InvalidOracleConfig,
+    #[msg("Price Account Does Not Match The Oracle Source Or Feed")]
+    InvalidPriceAccount,
+    #[msg("Mock Oracle Is Not Enabled In This Build")]
+    MockOracleDisabled,
+    #[msg("Price Feed Id Must Be 32 Non Zero Bytes In Hex")]
+    InvalidFeedId,
+    #[msg("Collateral Price Is Unavailable Or Disputed, Price Sensitive Actions Are Frozen")]
+    PriceFrozen,
+    #[msg("Secondary Price Feed Account Is Required For This Collateral")]
+    MissingSecondaryPriceFeed,
+    #[msg("Pyth Price Update Is Not Fully Verified")]
+    InsufficientVerification,
+}
+
+#[error_code]
+pub enum AdminError {
+    #[msg("Signer Is Not The Protocol Admin")]
+    Unauthorized,
+    #[msg("Signer Is Not The Proposed Admin")]
+    NotPendingAdmin,
+    #[msg("Fees, Base Rate Or Sigma Are Out Of Bounds")]
+    InvalidProtocolParameters,
+}
+
+#[error_code]
+pub enum PauseError {
+    #[msg("Opening And Borrowing Are Paused")]
+    OpenPaused,
+    #[msg("Repaying And Closing Are Paused")]
+    RepayPaused,
+    #[msg("Liquidations Are Paused")]
+    LiquidatePaused,
+    #[msg("Redemptions Are Paused")]
+    RedeemPaused,
+    #[msg("Staking Is Paused")]
+    StakePaused,
+    #[msg("Unstaking Is Paused")]
+    UnstakePaused,
+    #[msg("Claiming Rewards Is Paused")]
+    ClaimPaused,
+    #[msg("Interest Updates Are Paused")]
+    InterestUpdatePaused,
+    #[msg("Signer Is Neither The Guardian Nor The Admin")]
+    NotGuardian,
+    #[msg("Only The Admin Can Clear A Pause Flag")]
+    OnlyAdminCanUnpause,
 }
 
 #[error_code]
@@ -19,6 +93,32 @@ pub enum ArithmeticError {
     ArithmeticOverflow,
 }
 
+#[error_code]
+pub enum RedemptionError {
+    #[msg("Redemption Amount Must Be Greater Than Zero")]
+    InvalidAmount,
+    #[msg("Redemption Amount Exceeds The Debt Of All Positions")]
+    InsufficientDebtToRedeem,
+    #[msg("Position Has Insufficient Collateral For Redemption")]
+    InsufficientCollateral,
+    #[msg("Redemption Would Leave A Position Below The Minimum Debt")]
+    DebtBelowMinimum,
+}
+
+#[error_code]
+pub enum LiquidationError {
+    #[msg("Liquidation Amount Must Be Greater Than Zero")]
+    InvalidAmount,
+    #[msg("No Other Positions To Redistribute The Debt To")]
+    NoPositionsToRedistribute,
+    #[msg("Collateral Is Not Liquidated Through This Mode")]
+    InvalidLiquidationMode,
+    #[msg("Auction Has Nothing Left To Sell")]
+    AuctionFinished,
+    #[msg("Auction Has Not Reached Its Floor Price Yet")]
+    AuctionNotExpired,
+}
+
 #[error_code]
 pub enum StakeError {
     #[msg("Insufficient funds")]
@@ -29,4 +129,6 @@ pub enum StakeError {
     StakeAccountExists,
     #[msg("Stake account not found")]
     StakeAccountNotFound,
+    #[msg("Stake rewards must be claimed before unstaking")]
+    UnclaimedRewards,
 }
@@ -0,0 +1,261 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{AuctionParams, LiquidationMode, OracleConfig, PauseFlags, SecondaryOracle};
+
+#[event]
+pub struct MintFeeCharged {
+    pub user: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub debt_amount: u64,
+    pub fee: u64,
+}
+
+#[event]
+pub struct InterestCollected {
+    pub amount: u64,
+    pub treasury_amount: u64,
+    pub stability_pool_amount: u64,
+}
+
+#[event]
+pub struct ProtocolConfigInitialized {
+    pub admin: Pubkey,
+    pub stable_mint: Pubkey,
+    pub protocol_fee: u16,
+    pub redemption_fee: u16,
+    pub mint_fee: u16,
+    pub base_rate: u16,
+    pub sigma: u16,
+    pub debt_ceiling: u64,
+    pub stablecoin_price_feed: [u8; 32],
+    pub stablecoin_oracle: OracleConfig,
+}
+
+#[event]
+pub struct ProtocolConfigUpdated {
+    pub admin: Pubkey,
+    pub protocol_fee: u16,
+    pub redemption_fee: u16,
+    pub mint_fee: u16,
+    pub base_rate: u16,
+    pub sigma: u16,
+    pub debt_ceiling: u64,
+}
+
+#[event]
+pub struct StablecoinOracleUpdated {
+    pub stablecoin_price_feed: [u8; 32],
+    pub stablecoin_oracle: OracleConfig,
+}
+
+#[event]
+pub struct AdminProposed {
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+}
+
+#[event]
+pub struct AdminAccepted {
+    pub previous_admin: Pubkey,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct GuardianSet {
+    pub guardian: Pubkey,
+}
+
+// collateral_mint is None for the protocol wide flags
+#[event]
+pub struct PauseFlagsUpdated {
+    pub authority: Pubkey,
+    pub collateral_mint: Option<Pubkey>,
+    pub paused: PauseFlags,
+}
+
+#[event]
+pub struct CollateralListed {
+    pub collateral_mint: Pubkey,
+    pub collateral_price_feed: [u8; 32],
+    pub collateral_oracle: OracleConfig,
+    pub secondary_oracle: Option<SecondaryOracle>,
+    pub max_ltv: u16,
+    pub liquidation_threshold: u16,
+    pub liquidation_penalty: u16,
+    pub close_factor: u16,
+    pub liquidator_reward: u16,
+    pub liquidation_mode: LiquidationMode,
+    pub auction_params: AuctionParams,
+    pub debt_ceiling: u64,
+    pub min_debt: u64,
+}
+
+#[event]
+pub struct CollateralOracleUpdated {
+    pub collateral_mint: Pubkey,
+    pub collateral_price_feed: [u8; 32],
+    pub collateral_oracle: OracleConfig,
+    pub secondary_oracle: Option<SecondaryOracle>,
+}
+
+#[event]
+pub struct CollateralDebtLimitsUpdated {
+    pub collateral_mint: Pubkey,
+    pub debt_ceiling: u64,
+    pub min_debt: u64,
+}
+
+// Amounts after the change, debt includes the mint fee and is valued at
+// cumulative_interest_rate
+#[event]
+pub struct PositionOpened {
+    pub user: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub collateral_amount: u64,
+    pub debt_amount: u64,
+    pub mint_fee: u64,
+    pub cumulative_interest_rate: u128,
+}
+
+#[event]
+pub struct PositionAdjusted {
+    pub user: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub collateral_delta: i64,
+    pub debt_delta: i64,
+    pub mint_fee: u64,
+    pub collateral_amount: u64,
+    pub debt_amount: u64,
+    pub cumulative_interest_rate: u128,
+}
+
+#[event]
+pub struct PositionClosed {
+    pub user: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub collateral_returned: u64,
+    pub debt_repaid: u64,
+    pub cumulative_interest_rate: u128,
+}
+
+// debt_repaid is split into debt_burned against the stability pool and
+// debt_redistributed to the other positions, collateral_seized likewise after the
+// liquidator reward
+#[event]
+pub struct PositionLiquidated {
+    pub liquidator: Pubkey,
+    pub user: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub price: i64,
+    pub price_exponent: i32,
+    pub debt_repaid: u64,
+    pub debt_burned: u64,
+    pub debt_redistributed: u64,
+    pub collateral_seized: u64,
+    pub liquidator_reward: u64,
+    pub collateral_to_pool: u64,
+    pub collateral_redistributed: u64,
+    pub surplus_collateral: u64,
+    pub remaining_collateral: u64,
+    pub remaining_debt: u64,
+    // Bumped when the liquidation emptied the stability pool
+    pub stake_epoch: u64,
+    pub cumulative_interest_rate: u128,
+}
+
+#[event]
+pub struct PositionRedeemed {
+    pub redeemer: Pubkey,
+    pub user: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub price: i64,
+    pub price_exponent: i32,
+    pub debt_redeemed: u64,
+    pub collateral_redeemed: u64,
+    pub remaining_collateral: u64,
+    pub remaining_debt: u64,
+    pub cumulative_interest_rate: u128,
+}
+
+#[event]
+pub struct AuctionStarted {
+    pub keeper: Pubkey,
+    pub user: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub auction_id: u64,
+    pub collateral_amount: u64,
+    pub debt_amount: u64,
+    pub start_price: u64,
+    pub floor_price: u64,
+    pub price_exponent: i32,
+    pub cumulative_interest_rate: u128,
+}
+
+#[event]
+pub struct AuctionBid {
+    pub bidder: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub auction_id: u64,
+    pub price: u64,
+    pub collateral_bought: u64,
+    pub stable_paid: u64,
+    pub surplus_collateral: u64,
+    pub debt_redistributed: u64,
+    // Shortfall written off when no position was left to redistribute it to
+    pub bad_debt: u64,
+    pub remaining_collateral: u64,
+    pub remaining_debt: u64,
+    pub cumulative_interest_rate: u128,
+}
+
+#[event]
+pub struct AuctionReset {
+    pub user: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub auction_id: u64,
+    pub collateral_amount: u64,
+    pub debt_amount: u64,
+    pub start_price: u64,
+    pub floor_price: u64,
+    pub price_exponent: i32,
+    pub cumulative_interest_rate: u128,
+}
+
+// Rates are yearly in INTEREST_SCALE
+#[event]
+pub struct InterestRateUpdated {
+    pub old_rate: u128,
+    pub new_rate: u128,
+    pub cumulative_interest_rate: u128,
+    pub stablecoin_price: i64,
+    pub price_exponent: i32,
+    pub time_elapsed: u64,
+    pub total_debt: u128,
+    pub accrued_interest: u128,
+}
+
+#[event]
+pub struct Staked {
+    pub user: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub amount: u64,
+    pub stake_amount: u64,
+    pub total_stake_amount: u128,
+}
+
+#[event]
+pub struct Unstaked {
+    pub user: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub amount: u64,
+    pub total_stake_amount: u128,
+}
+
+#[event]
+pub struct RewardClaimed {
+    pub user: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub collateral_amount: u64,
+    pub interest_amount: u64,
+    pub stake_amount: u64,
+}
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+use crate::{errors::AdminError, events::AdminAccepted, state::ProtocolConfig};
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    pending_admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = protocol_config.bump,
+        constraint = protocol_config.pending_admin == Some(pending_admin.key()) @ AdminError::NotPendingAdmin,
+    )]
+    protocol_config: Account<'info, ProtocolConfig>,
+}
+
+impl<'info> AcceptAdmin<'info> {
+    pub fn accept_admin(&mut self) -> Result<()> {
+        let previous_admin = self.protocol_config.admin;
+
+        self.protocol_config.admin = self.pending_admin.key();
+        self.protocol_config.pending_admin = None;
+
+        emit!(AdminAccepted {
+            previous_admin,
+            admin: self.protocol_config.admin,
+        });
+
+        Ok(())
+    }
+}
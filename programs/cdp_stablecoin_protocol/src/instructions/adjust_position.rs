@@ -0,0 +1,331 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{burn, mint_to, transfer, Burn, Mint, MintTo, Token, TokenAccount, Transfer},
+};
+
+use crate::{
+    errors::{ArithmeticError, OracleError, PositionError},
+    events::{MintFeeCharged, PositionAdjusted},
+    oracle::get_guarded_price,
+    position_list::PositionList,
+    price_math,
+    state::{CollateralConfig, PauseAction, Position, ProtocolConfig},
+};
+
+#[derive(Accounts)]
+pub struct AdjustPosition<'info> {
+    #[account(mut)]
+    user: Signer<'info>,
+
+    collateral_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        address = protocol_config.stable_mint,
+        mint::decimals = 6,
+        mint::authority = auth,
+    )]
+    stable_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = protocol_config.bump
+    )]
+    protocol_config: Account<'info, ProtocolConfig>,
+    /// CHECK: This is an auth acc for the vault
+    #[account(
+        mut,
+        seeds = [b"auth"],
+        bump = protocol_config.auth_bump
+    )]
+    auth: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = user,
+    )]
+    user_ata: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = stable_mint,
+        associated_token::authority = user,
+    )]
+    user_stable_ata: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"collateral", collateral_mint.key().as_ref()],
+        constraint = collateral_vault_config.mint == collateral_mint.key(),
+        bump = collateral_vault_config.bump
+    )]
+    collateral_vault_config: Account<'info, CollateralConfig>,
+    #[account(
+        mut,
+        seeds = [b"position", user.key().as_ref(), collateral_mint.key().as_ref()],
+        bump,
+    )]
+    position: Box<Account<'info, Position>>,
+    /// CHECK: Owner and layout are checked by the oracle backend in the config
+    price_feed: UncheckedAccount<'info>,
+    /// CHECK: Only needed when the collateral has a secondary oracle, checked like price_feed
+    secondary_price_feed: Option<UncheckedAccount<'info>>,
+    #[account(
+        mut,
+        seeds = [b"collateral_vault", collateral_mint.key().as_ref()],
+        token::mint = collateral_mint,
+        token::authority = auth,
+        bump = collateral_vault_config.vault_bump
+    )]
+    collateral_vault: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"treasury", stable_mint.key().as_ref()],
+        token::mint = stable_mint,
+        token::authority = auth,
+        bump
+    )]
+    treasury_vault: Box<Account<'info, TokenAccount>>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> AdjustPosition<'info> {
+    pub fn adjust_position(
+        &mut self,
+        collateral_delta: i64,
+        debt_delta: i64,
+        remaining_accounts: &'info [AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(
+            collateral_delta != 0 || debt_delta != 0,
+            PositionError::InvalidAdjustment
+        );
+
+        // Withdrawing or borrowing more falls under the open switch, anything else under repay
+        let action = if collateral_delta < 0 || debt_delta > 0 {
+            PauseAction::Open
+        } else {
+            PauseAction::Repay
+        };
+        self.protocol_config.paused.check(action)?;
+        self.collateral_vault_config.paused.check(action)?;
+
+        // Settle accrued interest and any redistributed debt and collateral into the position
+        self.collateral_vault_config
+            .apply_pending_redistribution(&mut self.position, &self.protocol_config)?;
+
+        let current_debt = self.position.debt_amount;
+
+        let collateral_change = collateral_delta.unsigned_abs();
+        let debt_change = debt_delta.unsigned_abs();
+
+        // Borrowing more is charged the same origination fee as opening a position
+        let mint_fee = if debt_delta > 0 {
+            self.protocol_config.calculate_mint_fee(debt_change)?
+        } else {
+            0
+        };
+
+        let new_collateral_amount = if collateral_delta >= 0 {
+            self.position
+                .collateral_amount
+                .checked_add(collateral_change)
+                .ok_or(ArithmeticError::ArithmeticOverflow)?
+        } else {
+            self.position
+                .collateral_amount
+                .checked_sub(collateral_change)
+                .ok_or(PositionError::InvalidAdjustment)?
+        };
+
+        let new_debt_amount = if debt_delta >= 0 {
+            current_debt
+                .checked_add(debt_change)
+                .ok_or(ArithmeticError::ArithmeticOverflow)?
+                .checked_add(mint_fee)
+                .ok_or(ArithmeticError::ArithmeticOverflow)?
+        } else {
+            current_debt
+                .checked_sub(debt_change)
+                .ok_or(PositionError::InvalidAdjustment)?
+        };
+
+        if debt_delta != 0 {
+            self.collateral_vault_config
+                .check_min_debt(new_debt_amount)?;
+        }
+
+        // Only withdrawing collateral or borrowing more can make the position riskier
+        if (collateral_delta < 0 || debt_delta > 0) && new_debt_amount > 0 {
+            let price = get_guarded_price(
+                &self.price_feed,
+                self.secondary_price_feed.as_deref(),
+                &self.collateral_vault_config,
+            )?
+            .ok_or(OracleError::PriceFrozen)?;
+
+            let collateral_value = price_math::collateral_value(
+                new_collateral_amount,
+                price.lower_bound(),
+                price.exponent,
+                self.collateral_mint.decimals,
+            )?;
+
+            require!(collateral_value > 0, PositionError::InvalidLTV);
+
+            let ltv = price_math::ltv(new_debt_amount, collateral_value)?;
+
+            require!(
+                ltv <= self.collateral_vault_config.max_ltv as u128,
+                PositionError::InvalidLTV
+            );
+        }
+
+        let seeds = &[&b"auth"[..], &[self.protocol_config.auth_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        if collateral_delta > 0 {
+            let collateral_transfer_cpi_accounts = Transfer {
+                from: self.user_ata.to_account_info(),
+                to: self.collateral_vault.to_account_info(),
+                authority: self.user.to_account_info(),
+            };
+
+            let collateral_transfer_cpi_ctx = CpiContext::new(
+                self.token_program.to_account_info(),
+                collateral_transfer_cpi_accounts,
+            );
+
+            transfer(collateral_transfer_cpi_ctx, collateral_change)?;
+
+            self.collateral_vault_config.collateral_amount = self
+                .collateral_vault_config
+                .collateral_amount
+                .checked_add(collateral_change)
+                .ok_or(ArithmeticError::ArithmeticOverflow)?;
+        } else if collateral_delta < 0 {
+            let collateral_transfer_cpi_accounts = Transfer {
+                from: self.collateral_vault.to_account_info(),
+                to: self.user_ata.to_account_info(),
+                authority: self.auth.to_account_info(),
+            };
+
+            let collateral_transfer_cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                collateral_transfer_cpi_accounts,
+                signer_seeds,
+            );
+
+            transfer(collateral_transfer_cpi_ctx, collateral_change)?;
+
+            self.collateral_vault_config.collateral_amount = self
+                .collateral_vault_config
+                .collateral_amount
+                .checked_sub(collateral_change)
+                .ok_or(ArithmeticError::ArithmeticOverflow)?;
+        }
+
+        if debt_delta > 0 {
+            let accounts = MintTo {
+                mint: self.stable_mint.to_account_info(),
+                to: self.user_stable_ata.to_account_info(),
+                authority: self.auth.to_account_info(),
+            };
+
+            let stable_mint_cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                accounts,
+                signer_seeds,
+            );
+
+            mint_to(stable_mint_cpi_ctx, debt_change)?;
+
+            if mint_fee > 0 {
+                let accounts = MintTo {
+                    mint: self.stable_mint.to_account_info(),
+                    to: self.treasury_vault.to_account_info(),
+                    authority: self.auth.to_account_info(),
+                };
+
+                let fee_mint_cpi_ctx = CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    accounts,
+                    signer_seeds,
+                );
+
+                mint_to(fee_mint_cpi_ctx, mint_fee)?;
+
+                emit!(MintFeeCharged {
+                    user: self.user.key(),
+                    collateral_mint: self.collateral_mint.key(),
+                    debt_amount: debt_change,
+                    fee: mint_fee,
+                });
+            }
+        } else if debt_delta < 0 {
+            let accounts = Burn {
+                mint: self.stable_mint.to_account_info(),
+                from: self.user_stable_ata.to_account_info(),
+                authority: self.user.to_account_info(),
+            };
+
+            let stable_burn_cpi_ctx =
+                CpiContext::new(self.token_program.to_account_info(), accounts);
+
+            burn(stable_burn_cpi_ctx, debt_change)?;
+        }
+
+        let total_debt_change = debt_delta
+            .checked_add(mint_fee as i64)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+        self.protocol_config.update_totals(total_debt_change)?;
+        self.collateral_vault_config.update_debt(
+            total_debt_change,
+            self.protocol_config.cumulative_interest_rate,
+        )?;
+
+        if debt_delta > 0 {
+            self.collateral_vault_config
+                .check_debt_ceiling(self.protocol_config.cumulative_interest_rate)?;
+            self.protocol_config.check_debt_ceiling()?;
+        }
+
+        self.position.collateral_amount = new_collateral_amount;
+        self.position.debt_amount = new_debt_amount;
+
+        // Any change moves the position in the list, repaying all debt takes it out
+        let position_key = self.position.key();
+        let mut positions = PositionList::load(
+            &self.collateral_mint.key(),
+            &[position_key],
+            remaining_accounts,
+        )?;
+        positions.remove(
+            &mut self.collateral_vault_config,
+            position_key,
+            &mut self.position,
+        )?;
+        positions.insert(
+            &mut self.collateral_vault_config,
+            position_key,
+            &mut self.position,
+        )?;
+        positions.exit()?;
+
+        emit!(PositionAdjusted {
+            user: self.user.key(),
+            collateral_mint: self.collateral_mint.key(),
+            collateral_delta,
+            debt_delta,
+            mint_fee,
+            collateral_amount: new_collateral_amount,
+            debt_amount: new_debt_amount,
+            cumulative_interest_rate: self.protocol_config.cumulative_interest_rate,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,243 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{burn, transfer, Burn, Mint, Token, TokenAccount, Transfer},
+};
+
+use crate::{
+    errors::{ArithmeticError, LiquidationError},
+    events::AuctionBid,
+    price_math,
+    state::{Auction, CollateralConfig, PauseAction, ProtocolConfig},
+};
+
+#[derive(Accounts)]
+pub struct BidAuction<'info> {
+    #[account(mut)]
+    bidder: Signer<'info>,
+
+    /// CHECK: Receives any collateral left once the auction debt is covered
+    #[account(address = auction.owner)]
+    owner: UncheckedAccount<'info>,
+
+    /// CHECK: Paid the auction rent and gets it back when the auction closes
+    #[account(mut, address = auction.keeper)]
+    keeper: UncheckedAccount<'info>,
+
+    collateral_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        address = protocol_config.stable_mint
+    )]
+    stable_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = protocol_config.bump
+    )]
+    protocol_config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: This is an auth acc for the vault
+    #[account(
+        seeds = [b"auth"],
+        bump = protocol_config.auth_bump
+    )]
+    auth: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = bidder,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = bidder,
+    )]
+    bidder_ata: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = stable_mint,
+        associated_token::authority = bidder,
+    )]
+    bidder_stable_ata: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = bidder,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = owner,
+    )]
+    owner_ata: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"collateral", collateral_mint.key().as_ref()],
+        bump = collateral_vault_config.bump
+    )]
+    collateral_vault_config: Account<'info, CollateralConfig>,
+    #[account(
+        mut,
+        seeds = [b"auction", collateral_mint.key().as_ref(), auction.id.to_le_bytes().as_ref()],
+        bump = auction.bump
+    )]
+    auction: Account<'info, Auction>,
+    #[account(
+        mut,
+        seeds = [b"collateral_vault", collateral_mint.key().as_ref()],
+        token::mint = collateral_mint,
+        token::authority = auth,
+        bump = collateral_vault_config.vault_bump
+    )]
+    collateral_vault: Box<Account<'info, TokenAccount>>,
+
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> BidAuction<'info> {
+    // Buy up to collateral_amount at the current auction price, the stablecoin paid is
+    // burned against the auction debt
+    pub fn bid_auction(&mut self, collateral_amount: u64) -> Result<()> {
+        self.protocol_config.paused.check(PauseAction::Liquidate)?;
+        self.collateral_vault_config
+            .paused
+            .check(PauseAction::Liquidate)?;
+
+        require!(collateral_amount > 0, LiquidationError::InvalidAmount);
+        require!(
+            self.auction.collateral_amount > 0,
+            LiquidationError::AuctionFinished
+        );
+
+        let current_debt = self
+            .auction
+            .calculate_current_debt(self.protocol_config.cumulative_interest_rate)?;
+
+        let price = self.auction.current_price(Clock::get()?.unix_timestamp)? as i64;
+        let exponent = self.auction.price_exponent;
+        let decimals = self.collateral_mint.decimals;
+
+        let mut collateral_bought = collateral_amount.min(self.auction.collateral_amount);
+
+        let mut stable_paid =
+            price_math::collateral_value(collateral_bought, price, exponent, decimals)?
+                .min(u64::MAX as u128) as u64;
+
+        // Never sell more collateral than the remaining debt is worth
+        if stable_paid > current_debt {
+            stable_paid = current_debt;
+            collateral_bought =
+                price_math::collateral_amount(current_debt, price, exponent, decimals)?
+                    .min(self.auction.collateral_amount as u128) as u64;
+        }
+
+        require!(stable_paid > 0, LiquidationError::InvalidAmount);
+
+        let accounts = Burn {
+            mint: self.stable_mint.to_account_info(),
+            from: self.bidder_stable_ata.to_account_info(),
+            authority: self.bidder.to_account_info(),
+        };
+
+        let stable_burn_cpi_ctx = CpiContext::new(self.token_program.to_account_info(), accounts);
+
+        burn(stable_burn_cpi_ctx, stable_paid)?;
+
+        self.protocol_config.update_totals(-(stable_paid as i64))?;
+        self.collateral_vault_config.update_debt(
+            -(stable_paid as i64),
+            self.protocol_config.cumulative_interest_rate,
+        )?;
+
+        let seeds = &[&b"auth"[..], &[self.protocol_config.auth_bump]];
+
+        let signer_seeds = &[&seeds[..]];
+
+        let collateral_transfer_cpi_accounts = Transfer {
+            from: self.collateral_vault.to_account_info(),
+            to: self.bidder_ata.to_account_info(),
+            authority: self.auth.to_account_info(),
+        };
+
+        let collateral_transfer_cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            collateral_transfer_cpi_accounts,
+            signer_seeds,
+        );
+
+        transfer(collateral_transfer_cpi_ctx, collateral_bought)?;
+
+        self.auction.collateral_amount = self
+            .auction
+            .collateral_amount
+            .checked_sub(collateral_bought)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+        self.auction.debt_amount = current_debt
+            .checked_sub(stable_paid)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+        self.auction.prev_cumulative_interest_rate = self.protocol_config.cumulative_interest_rate;
+
+        let mut surplus_collateral = 0;
+        let mut debt_redistributed = 0;
+        let mut bad_debt = 0;
+
+        if self.auction.debt_amount == 0 {
+            // Collateral left after the debt is covered goes back to the borrower
+            surplus_collateral = self.auction.collateral_amount;
+
+            if surplus_collateral > 0 {
+                let surplus_transfer_cpi_accounts = Transfer {
+                    from: self.collateral_vault.to_account_info(),
+                    to: self.owner_ata.to_account_info(),
+                    authority: self.auth.to_account_info(),
+                };
+
+                let surplus_transfer_cpi_ctx = CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    surplus_transfer_cpi_accounts,
+                    signer_seeds,
+                );
+
+                transfer(surplus_transfer_cpi_ctx, surplus_collateral)?;
+
+                self.auction.collateral_amount = 0;
+            }
+
+            self.auction.close(self.keeper.to_account_info())?;
+        } else if self.auction.collateral_amount == 0 {
+            // Sold out below the debt, the shortfall is spread over the remaining positions.
+            // With none left it is written off rather than leaving the auction unclosable
+            if self.collateral_vault_config.collateral_amount > 0 {
+                debt_redistributed = self.auction.debt_amount;
+
+                self.collateral_vault_config.redistribute(
+                    0,
+                    self.auction.debt_amount,
+                    0,
+                    self.protocol_config.cumulative_interest_rate,
+                )?;
+            } else {
+                bad_debt = self.auction.debt_amount;
+
+                self.collateral_vault_config
+                    .write_off_debt(bad_debt, &mut self.protocol_config)?;
+            }
+
+            self.auction.close(self.keeper.to_account_info())?;
+        }
+
+        emit!(AuctionBid {
+            bidder: self.bidder.key(),
+            collateral_mint: self.collateral_mint.key(),
+            auction_id: self.auction.id,
+            price: price as u64,
+            collateral_bought,
+            stable_paid,
+            surplus_collateral,
+            debt_redistributed,
+            bad_debt,
+            remaining_collateral: self.auction.collateral_amount,
+            remaining_debt: self.auction.debt_amount,
+            cumulative_interest_rate: self.protocol_config.cumulative_interest_rate,
+        });
+
+        Ok(())
+    }
+}
@@ -5,9 +5,8 @@ use anchor_spl::{
 };
 
 use crate::{
-    constants::BPS_SCALE,
-    errors::ArithmeticError,
-    state::{CollateralConfig, ProtocolConfig, StakeAccount},
+    events::RewardClaimed,
+    state::{CollateralConfig, PauseAction, ProtocolConfig, StakeAccount},
 };
 
 #[derive(Accounts)]
@@ -17,6 +16,11 @@ pub struct ClaimStakeRewards<'info> {
 
     collateral_mint: Account<'info, Mint>,
 
+    #[account(
+        address = protocol_config.stable_mint
+    )]
+    stable_mint: Account<'info, Mint>,
+
     #[account(
         mut,
         associated_token::mint = collateral_mint,
@@ -24,6 +28,13 @@ pub struct ClaimStakeRewards<'info> {
     )]
     user_ata: Box<Account<'info, TokenAccount>>,
 
+    #[account(
+        mut,
+        associated_token::mint = stable_mint,
+        associated_token::authority = user,
+    )]
+    user_stable_ata: Box<Account<'info, TokenAccount>>,
+
     #[account(
         seeds = [b"config"],
         bump = protocol_config.bump
@@ -52,6 +63,15 @@ pub struct ClaimStakeRewards<'info> {
     )]
     liquidation_rewards_vault: Account<'info, TokenAccount>,
 
+    #[account(
+        mut,
+        seeds = [b"interest_rewards_vault", stable_mint.key().as_ref()],
+        token::mint = stable_mint,
+        token::authority = auth,
+        bump
+    )]
+    interest_rewards_vault: Box<Account<'info, TokenAccount>>,
+
     #[account(
         mut,
         seeds = [b"stake", user.key().as_ref(), collateral_vault_config.mint.key().as_ref()],
@@ -66,6 +86,11 @@ pub struct ClaimStakeRewards<'info> {
 
 impl<'info> ClaimStakeRewards<'info> {
     pub fn claim_stake_reward(&mut self) -> Result<()> {
+        self.protocol_config.paused.check(PauseAction::Claim)?;
+        self.collateral_vault_config
+            .paused
+            .check(PauseAction::Claim)?;
+
         let stake_reward_transfer_cpi_accounts = Transfer {
             from: self.liquidation_rewards_vault.to_account_info(),
             to: self.user_ata.to_account_info(),
@@ -81,36 +106,55 @@ impl<'info> ClaimStakeRewards<'info> {
             signer_seeds,
         );
 
-        let amount = (self.stake_account.amount as u128)
-            .checked_mul(
-                self.collateral_vault_config
-                    .gain_summation
-                    .checked_sub(self.stake_account.init_gain_summation)
-                    .ok_or(ArithmeticError::ArithmeticOverflow)?,
-            )
-            .ok_or(ArithmeticError::ArithmeticOverflow)?
-            .checked_mul(BPS_SCALE as u128)
-            .ok_or(ArithmeticError::ArithmeticOverflow)?
-            .checked_div(self.stake_account.init_deposit_depletion_factor as u128)
-            .ok_or(ArithmeticError::ArithmeticOverflow)? as u64;
+        let amount = self
+            .stake_account
+            .collateral_gain(&self.collateral_vault_config)?;
 
         transfer(stake_reward_transfer_cpi_ctx, amount)?;
 
+        // Stablecoin interest distributed by collect_interest
+        let interest_amount = self.stake_account.interest_gain(&self.protocol_config)?;
+
+        if interest_amount > 0 {
+            let interest_transfer_cpi_accounts = Transfer {
+                from: self.interest_rewards_vault.to_account_info(),
+                to: self.user_stable_ata.to_account_info(),
+                authority: self.auth.to_account_info(),
+            };
+
+            let interest_transfer_cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                interest_transfer_cpi_accounts,
+                signer_seeds,
+            );
+
+            transfer(interest_transfer_cpi_ctx, interest_amount)?;
+        }
+
         let updated_stake_amount = self
             .stake_account
-            .amount
-            .checked_mul(self.protocol_config.deposit_depletion_factor as u64)
-            .ok_or(ArithmeticError::ArithmeticOverflow)?
-            .checked_div(self.stake_account.init_deposit_depletion_factor as u64)
-            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+            .compounded_amount(&self.protocol_config)?;
 
         self.stake_account.init_deposit_depletion_factor =
             self.protocol_config.deposit_depletion_factor;
 
         self.stake_account.init_gain_summation = self.collateral_vault_config.gain_summation;
 
+        self.stake_account.init_interest_gain_summation =
+            self.protocol_config.interest_gain_summation;
+
+        self.stake_account.epoch = self.protocol_config.stake_epoch;
+
         self.stake_account.amount = updated_stake_amount;
 
+        emit!(RewardClaimed {
+            user: self.user.key(),
+            collateral_mint: self.collateral_mint.key(),
+            collateral_amount: amount,
+            interest_amount,
+            stake_amount: updated_stake_amount,
+        });
+
         Ok(())
     }
 }
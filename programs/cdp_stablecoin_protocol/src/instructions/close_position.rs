@@ -1,14 +1,16 @@
-use anchor_lang::{prelude::*, solana_program::native_token::LAMPORTS_PER_SOL};
+use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
     token::{burn, transfer, Burn, Mint, Token, TokenAccount, Transfer},
 };
-use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
 
 use crate::{
-    constants::MAX_LTV,
     errors::{ArithmeticError, PositionError},
-    state::{CollateralConfig, Position, ProtocolConfig},
+    events::PositionClosed,
+    oracle::get_guarded_price,
+    position_list::PositionList,
+    price_math,
+    state::{CollateralConfig, PauseAction, Position, ProtocolConfig},
 };
 
 #[derive(Accounts)]
@@ -26,6 +28,11 @@ pub struct ClosePosition<'info> {
     )]
     stable_mint: Account<'info, Mint>,
 
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = protocol_config.bump
+    )]
     protocol_config: Account<'info, ProtocolConfig>,
 
     /// CHECK: This is an auth acc for the vault
@@ -61,8 +68,10 @@ pub struct ClosePosition<'info> {
     )]
     position: Account<'info, Position>,
 
-    #[account(owner = pyth_solana_receiver_sdk::ID)]
-    price_feed: Account<'info, PriceUpdateV2>,
+    /// CHECK: Owner and layout are checked by the oracle backend in the config
+    price_feed: UncheckedAccount<'info>,
+    /// CHECK: Only needed when the collateral has a secondary oracle, checked like price_feed
+    secondary_price_feed: Option<UncheckedAccount<'info>>,
 
     #[account(
         mut,
@@ -79,36 +88,62 @@ pub struct ClosePosition<'info> {
 }
 
 impl<'info> ClosePosition<'info> {
-    pub fn close_position(&mut self) -> Result<()> {
+    pub fn close_position(
+        &mut self,
+        remaining_accounts: &'info [AccountInfo<'info>],
+    ) -> Result<()> {
+        self.protocol_config.paused.check(PauseAction::Repay)?;
+        self.collateral_vault_config
+            .paused
+            .check(PauseAction::Repay)?;
+
         // require!(MIN_INTEREST_RATE<= interest_rate && interest_rate <= MAX_INTEREST_RATE, PositionError::InvalidInterestRate);
         // require!(MIN_LTV <= ltv && ltv <= MAX_LTV, PositionError::InvalidLTV);
 
-        let current_debt = self
-            .protocol_config
-            .calculate_current_debt(&self.position)?;
-
-        let price_feed = &self.price_feed;
-        // let maximum_age: u64 = 30;
-        let feed_id: [u8; 32] =
-            get_feed_id_from_hex(&self.collateral_vault_config.collateral_price_feed)?;
-        // let price = price_feed.get_price_no_older_than(&Clock::get()?, maximum_age, &feed_id)?;
-        let price = price_feed.get_price_unchecked(&feed_id)?;
-
-        let collateral_value = (price.price as u128)
-            .checked_mul(self.position.collateral_amount as u128)
-            .ok_or(ArithmeticError::ArithmeticOverflow)?
-            .checked_div(10_u128.pow(price.exponent.abs() as u32))
-            .ok_or(ArithmeticError::ArithmeticOverflow)?
-            .checked_div(LAMPORTS_PER_SOL as u128)
-            .ok_or(ArithmeticError::ArithmeticOverflow)?;
-
-        let ltv = (current_debt as u128)
-            .checked_mul(10000)
-            .ok_or(ArithmeticError::ArithmeticOverflow)?
-            .checked_div(collateral_value as u128)
-            .ok_or(ArithmeticError::ArithmeticOverflow)? as u16;
-
-        if MAX_LTV >= ltv {
+        // Settle accrued interest and any redistributed debt and collateral into the position
+        self.collateral_vault_config
+            .apply_pending_redistribution(&mut self.position, &self.protocol_config)?;
+
+        let current_debt = self.position.debt_amount;
+        let price = get_guarded_price(
+            &self.price_feed,
+            self.secondary_price_feed.as_deref(),
+            &self.collateral_vault_config,
+        )?;
+
+        // Repaying the whole debt leaves the protocol no risk, so a frozen oracle does
+        // not stop a borrower from closing
+        let closable = match price {
+            Some(price) => {
+                let collateral_value = price_math::collateral_value(
+                    self.position.collateral_amount,
+                    price.upper_bound(),
+                    price.exponent,
+                    self.collateral_mint.decimals,
+                )?;
+
+                price_math::ltv(current_debt, collateral_value)?
+                    <= self.collateral_vault_config.liquidation_threshold as u128
+            }
+            None => true,
+        };
+
+        if closable {
+            let collateral_returned = self.position.collateral_amount;
+
+            let position_key = self.position.key();
+            let mut positions = PositionList::load(
+                &self.collateral_mint.key(),
+                &[position_key],
+                remaining_accounts,
+            )?;
+            positions.remove(
+                &mut self.collateral_vault_config,
+                position_key,
+                &mut self.position,
+            )?;
+            positions.exit()?;
+
             let collateral_transfer_cpi_accounts = Transfer {
                 from: self.collateral_vault.to_account_info(),
                 to: self.user_ata.to_account_info(),
@@ -145,6 +180,18 @@ impl<'info> ClosePosition<'info> {
             burn(stable_burn_cpi_ctx, current_debt)?; // Use current_debt with accrued interest
 
             self.protocol_config.update_totals(-(current_debt as i64))?;
+            self.collateral_vault_config.update_debt(
+                -(current_debt as i64),
+                self.protocol_config.cumulative_interest_rate,
+            )?;
+
+            emit!(PositionClosed {
+                user: self.user.key(),
+                collateral_mint: self.collateral_mint.key(),
+                collateral_returned,
+                debt_repaid: current_debt,
+                cumulative_interest_rate: self.protocol_config.cumulative_interest_rate,
+            });
         } else {
             return err!(PositionError::InvalidLTV);
         }
@@ -0,0 +1,151 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{mint_to, Mint, MintTo, Token, TokenAccount};
+
+use crate::{
+    constants::{BPS_SCALE, INTEREST_SCALE},
+    errors::ArithmeticError,
+    events::InterestCollected,
+    state::{PauseAction, ProtocolConfig},
+};
+
+#[derive(Accounts)]
+pub struct CollectInterest<'info> {
+    #[account(mut)]
+    user: Signer<'info>,
+    #[account(
+        mut,
+        address = protocol_config.stable_mint,
+        mint::decimals = 6,
+        mint::authority = auth,
+    )]
+    stable_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = protocol_config.bump
+    )]
+    protocol_config: Account<'info, ProtocolConfig>,
+    /// CHECK: This is an auth acc for the vault
+    #[account(
+        seeds = [b"auth"],
+        bump = protocol_config.auth_bump
+    )]
+    auth: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"treasury", stable_mint.key().as_ref()],
+        token::mint = stable_mint,
+        token::authority = auth,
+        bump
+    )]
+    treasury_vault: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"interest_rewards_vault", stable_mint.key().as_ref()],
+        token::mint = stable_mint,
+        token::authority = auth,
+        bump
+    )]
+    interest_rewards_vault: Box<Account<'info, TokenAccount>>,
+    token_program: Program<'info, Token>,
+}
+
+impl<'info> CollectInterest<'info> {
+    // Mint interest accrued by update_interest_rate, protocol_fee goes to the treasury
+    // and the rest to stability pool stakers
+    pub fn collect_interest(&mut self) -> Result<()> {
+        self.protocol_config
+            .paused
+            .check(PauseAction::InterestUpdate)?;
+
+        let amount = self.protocol_config.accrued_interest.min(u64::MAX as u128) as u64;
+
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let total_stake_amount = self.protocol_config.total_stake_amount;
+
+        let stability_pool_amount = match total_stake_amount {
+            0 => 0,
+            _ => (amount as u128)
+                .checked_mul(
+                    BPS_SCALE
+                        .checked_sub(self.protocol_config.protocol_fee)
+                        .ok_or(ArithmeticError::ArithmeticOverflow)? as u128,
+                )
+                .ok_or(ArithmeticError::ArithmeticOverflow)?
+                .checked_div(BPS_SCALE as u128)
+                .ok_or(ArithmeticError::ArithmeticOverflow)? as u64,
+        };
+
+        let treasury_amount = amount
+            .checked_sub(stability_pool_amount)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+        let seeds = &[&b"auth"[..], &[self.protocol_config.auth_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        if treasury_amount > 0 {
+            let accounts = MintTo {
+                mint: self.stable_mint.to_account_info(),
+                to: self.treasury_vault.to_account_info(),
+                authority: self.auth.to_account_info(),
+            };
+
+            let treasury_mint_cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                accounts,
+                signer_seeds,
+            );
+
+            mint_to(treasury_mint_cpi_ctx, treasury_amount)?;
+        }
+
+        if stability_pool_amount > 0 {
+            let accounts = MintTo {
+                mint: self.stable_mint.to_account_info(),
+                to: self.interest_rewards_vault.to_account_info(),
+                authority: self.auth.to_account_info(),
+            };
+
+            let stakers_mint_cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                accounts,
+                signer_seeds,
+            );
+
+            mint_to(stakers_mint_cpi_ctx, stability_pool_amount)?;
+
+            self.protocol_config.interest_gain_summation = self
+                .protocol_config
+                .interest_gain_summation
+                .checked_add(
+                    (stability_pool_amount as u128)
+                        .checked_mul(INTEREST_SCALE)
+                        .ok_or(ArithmeticError::ArithmeticOverflow)?
+                        .checked_mul(self.protocol_config.deposit_depletion_factor as u128)
+                        .ok_or(ArithmeticError::ArithmeticOverflow)?
+                        .checked_div(BPS_SCALE as u128)
+                        .ok_or(ArithmeticError::ArithmeticOverflow)?
+                        .checked_div(total_stake_amount)
+                        .ok_or(ArithmeticError::ArithmeticOverflow)?,
+                )
+                .ok_or(ArithmeticError::ArithmeticOverflow)?;
+        }
+
+        self.protocol_config.accrued_interest = self
+            .protocol_config
+            .accrued_interest
+            .checked_sub(amount as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+        emit!(InterestCollected {
+            amount,
+            treasury_amount,
+            stability_pool_amount,
+        });
+
+        Ok(())
+    }
+}
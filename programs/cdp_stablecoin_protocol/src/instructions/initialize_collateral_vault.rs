@@ -1,7 +1,15 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, Token, TokenAccount};
 
-use crate::state::{CollateralConfig, ProtocolConfig};
+use crate::{
+    errors::{AdminError, OracleError},
+    events::CollateralListed,
+    oracle::{get_guarded_price, parse_feed_id},
+    state::{
+        AuctionParams, CollateralConfig, LiquidationMode, OracleConfig, PauseFlags, ProtocolConfig,
+        SecondaryOracle,
+    },
+};
 
 #[derive(Accounts)]
 pub struct InitializeCollateralVault<'info> {
@@ -20,7 +28,8 @@ pub struct InitializeCollateralVault<'info> {
 
     #[account(
         seeds = [b"config"],
-        bump = protocol_config.bump
+        bump = protocol_config.bump,
+        has_one = admin @ AdminError::Unauthorized,
     )]
     protocol_config: Account<'info, ProtocolConfig>,
     /// CHECK: This is an auth acc for the vault
@@ -63,27 +72,103 @@ pub struct InitializeCollateralVault<'info> {
         bump
     )]
     stake_vault: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Owner and layout are checked by the oracle backend in the config
+    price_feed: UncheckedAccount<'info>,
+    /// CHECK: Only needed when the collateral has a secondary oracle, checked like price_feed
+    secondary_price_feed: Option<UncheckedAccount<'info>>,
     token_program: Program<'info, Token>,
     system_program: Program<'info, System>,
 }
 
 impl<'info> InitializeCollateralVault<'info> {
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize_collateral_vault(
         &mut self,
         collateral_price_feed: String,
+        collateral_oracle: OracleConfig,
+        secondary_oracle: Option<SecondaryOracle>,
+        max_ltv: u16,
+        liquidation_threshold: u16,
+        liquidation_penalty: u16,
+        close_factor: u16,
+        liquidator_reward: u16,
+        liquidation_mode: LiquidationMode,
+        auction_params: AuctionParams,
+        debt_ceiling: u64,
+        min_debt: u64,
         bumps: &InitializeCollateralVaultBumps,
     ) -> Result<()> {
+        CollateralConfig::validate_risk_parameters(
+            max_ltv,
+            liquidation_threshold,
+            liquidation_penalty,
+            close_factor,
+            liquidator_reward,
+        )?;
+
+        auction_params.validate()?;
+        collateral_oracle.validate()?;
+        if let Some(secondary_oracle) = secondary_oracle {
+            secondary_oracle.validate()?;
+        }
+        let collateral_price_feed = parse_feed_id(&collateral_price_feed)?;
+
         self.collateral_vault_config.set_inner(CollateralConfig {
             mint: self.collateral_mint.key(),
             collateral_price_feed,
+            collateral_oracle,
+            secondary_oracle,
             vault: self.collateral_vault.key(),
             collateral_amount: 0,
             stability_pool_rewards_amount: 0,
             gain_summation: 0,
+            max_ltv,
+            liquidation_threshold,
+            liquidation_penalty,
+            close_factor,
+            liquidator_reward,
+            redistributed_collateral_per_unit: 0,
+            redistributed_debt_per_unit: 0,
+            liquidation_mode,
+            auction_params,
+            auction_count: 0,
+            paused: PauseFlags::default(),
+            debt_ceiling,
+            normalized_debt: 0,
+            min_debt,
+            stake_epoch: self.protocol_config.stake_epoch,
+            prev_stake_epoch: self.protocol_config.stake_epoch,
+            prev_epoch_gain_summation: 0,
+            bad_debt: 0,
+            riskiest_position: None,
             bump: bumps.collateral_vault_config,
             vault_bump: bumps.collateral_vault,
         });
 
+        // A feed that cannot be read now would leave the collateral unusable
+        get_guarded_price(
+            &self.price_feed,
+            self.secondary_price_feed.as_deref(),
+            &self.collateral_vault_config,
+        )?
+        .ok_or(OracleError::PriceFrozen)?;
+
+        emit!(CollateralListed {
+            collateral_mint: self.collateral_mint.key(),
+            collateral_price_feed,
+            collateral_oracle,
+            secondary_oracle,
+            max_ltv,
+            liquidation_threshold,
+            liquidation_penalty,
+            close_factor,
+            liquidator_reward,
+            liquidation_mode,
+            auction_params,
+            debt_ceiling,
+            min_debt,
+        });
+
         Ok(())
     }
 }
@@ -1,12 +1,26 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token};
+use anchor_spl::token::{Mint, Token, TokenAccount};
 
-use crate::{constants::BPS_SCALE, state::ProtocolConfig};
+use crate::{
+    constants::{BPS_SCALE, INTEREST_SCALE},
+    errors::AdminError,
+    events::ProtocolConfigInitialized,
+    oracle::{get_price, parse_feed_id},
+    program::CdpStablecoinProtocol,
+    state::{OracleConfig, PauseFlags, ProtocolConfig},
+};
 
 #[derive(Accounts)]
 pub struct InitializeProtocolConfig<'info> {
     #[account(mut)]
     admin: Signer<'info>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    program: Program<'info, CdpStablecoinProtocol>,
+    // Only the upgrade authority can initialise, so nobody can front-run the deployment
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ AdminError::Unauthorized
+    )]
+    program_data: Account<'info, ProgramData>,
     #[account(
         init,
         space = 8 + ProtocolConfig::INIT_SPACE,
@@ -32,11 +46,32 @@ pub struct InitializeProtocolConfig<'info> {
         bump
     )]
     auth: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"treasury", stable_mint.key().as_ref()],
+        token::mint = stable_mint,
+        token::authority = auth,
+        bump
+    )]
+    treasury_vault: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"interest_rewards_vault", stable_mint.key().as_ref()],
+        token::mint = stable_mint,
+        token::authority = auth,
+        bump
+    )]
+    interest_rewards_vault: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Owner and layout are checked by the oracle backend in stablecoin_oracle
+    price_feed: UncheckedAccount<'info>,
     token_program: Program<'info, Token>,
     system_program: Program<'info, System>,
 }
 
 impl<'info> InitializeProtocolConfig<'info> {
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize_protocol_config(
         &mut self,
         protocol_fee: u16,
@@ -44,10 +79,29 @@ impl<'info> InitializeProtocolConfig<'info> {
         mint_fee: u16,
         base_rate: u16,
         sigma: u16,
+        debt_ceiling: u64,
         stablecoin_price_feed: String,
+        stablecoin_oracle: OracleConfig,
         bumps: &InitializeProtocolConfigBumps,
     ) -> Result<()> {
+        ProtocolConfig::validate_parameters(
+            protocol_fee,
+            redemption_fee,
+            mint_fee,
+            base_rate,
+            sigma,
+        )?;
+        stablecoin_oracle.validate()?;
+        let stablecoin_price_feed = parse_feed_id(&stablecoin_price_feed)?;
+
+        // A feed that cannot be read would stop the interest rate controller
+        get_price(&self.price_feed, &stablecoin_price_feed, &stablecoin_oracle)?;
+
         self.protocol_config.set_inner(ProtocolConfig {
+            admin: self.admin.key(),
+            pending_admin: None,
+            guardian: self.admin.key(),
+            paused: PauseFlags::default(),
             stable_mint: self.stable_mint.key(),
             protocol_fee,
             redemption_fee,
@@ -58,10 +112,30 @@ impl<'info> InitializeProtocolConfig<'info> {
             bump: bumps.protocol_config,
             cumulative_interest_rate: ProtocolConfig::INITIAL_CUMULATIVE_RATE,
             stablecoin_price_feed,
+            stablecoin_oracle,
             last_interest_rate_update: Clock::get()?.unix_timestamp,
+            interest_rate: (base_rate as u128) * INTEREST_SCALE / BPS_SCALE as u128,
             deposit_depletion_factor: BPS_SCALE,
             total_debt: 0,
+            debt_ceiling,
             total_stake_amount: 0,
+            accrued_interest: 0,
+            interest_gain_summation: 0,
+            stake_epoch: 0,
+            prev_epoch_interest_gain_summation: 0,
+        });
+
+        emit!(ProtocolConfigInitialized {
+            admin: self.admin.key(),
+            stable_mint: self.stable_mint.key(),
+            protocol_fee,
+            redemption_fee,
+            mint_fee,
+            base_rate,
+            sigma,
+            debt_ceiling,
+            stablecoin_price_feed,
+            stablecoin_oracle,
         });
 
         Ok(())
@@ -1,14 +1,17 @@
-use anchor_lang::{prelude::*, solana_program::native_token::LAMPORTS_PER_SOL};
+use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
     token::{burn, transfer, Burn, Mint, Token, TokenAccount, Transfer},
 };
-use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
 
 use crate::{
-    constants::{BPS_SCALE, MAX_LTV},
-    errors::{ArithmeticError, PositionError},
-    state::{CollateralConfig, Position, ProtocolConfig},
+    constants::BPS_SCALE,
+    errors::{ArithmeticError, LiquidationError, OracleError, PositionError},
+    events::PositionLiquidated,
+    oracle::get_guarded_price,
+    position_list::PositionList,
+    price_math,
+    state::{CollateralConfig, LiquidationMode, PauseAction, Position, ProtocolConfig},
 };
 
 #[derive(Accounts)]
@@ -42,7 +45,8 @@ pub struct LiquidatePosition<'info> {
     )]
     auth: UncheckedAccount<'info>,
     #[account(
-        mut,
+        init_if_needed,
+        payer = liquidator,
         associated_token::mint = collateral_mint,
         associated_token::authority = user,
     )]
@@ -53,6 +57,13 @@ pub struct LiquidatePosition<'info> {
         associated_token::authority = user,
     )]
     user_stable_ata: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = liquidator,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = liquidator,
+    )]
+    liquidator_ata: Box<Account<'info, TokenAccount>>,
     #[account(
         mut,
         seeds = [b"collateral", collateral_mint.key().as_ref()],
@@ -61,14 +72,15 @@ pub struct LiquidatePosition<'info> {
     collateral_vault_config: Account<'info, CollateralConfig>,
     #[account(
         mut,
-        close = user,
         seeds = [b"position", user.key().as_ref(), collateral_mint.key().as_ref()],
         bump,
     )]
     position: Account<'info, Position>,
 
-    #[account(owner = pyth_solana_receiver_sdk::ID)]
-    price_feed: Account<'info, PriceUpdateV2>,
+    /// CHECK: Owner and layout are checked by the oracle backend in the config
+    price_feed: UncheckedAccount<'info>,
+    /// CHECK: Only needed when the collateral has a secondary oracle, checked like price_feed
+    secondary_price_feed: Option<UncheckedAccount<'info>>,
 
     #[account(
         mut,
@@ -103,111 +115,294 @@ pub struct LiquidatePosition<'info> {
 }
 
 impl<'info> LiquidatePosition<'info> {
-    pub fn liquidate_position(&mut self) -> Result<()> {
-        let current_debt = self
-            .protocol_config
-            .calculate_current_debt(&self.position)?;
-
-        let price_feed = &self.price_feed;
-        // let maximum_age: u64 = 30;
-        let feed_id: [u8; 32] =
-            get_feed_id_from_hex(&self.collateral_vault_config.collateral_price_feed)?;
-        // let price = price_feed.get_price_no_older_than(&Clock::get()?, maximum_age, &feed_id)?;
-        let price = price_feed.get_price_unchecked(&feed_id)?;
-
-        let collateral_value = (price.price as u128)
-            .checked_mul(self.position.collateral_amount as u128)
-            .ok_or(ArithmeticError::ArithmeticOverflow)?
-            .checked_div(10_u128.pow(price.exponent.abs() as u32))
-            .ok_or(ArithmeticError::ArithmeticOverflow)?
-            .checked_div(LAMPORTS_PER_SOL as u128)
-            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+    pub fn liquidate_position(
+        &mut self,
+        debt_amount: u64,
+        remaining_accounts: &'info [AccountInfo<'info>],
+    ) -> Result<()> {
+        self.protocol_config.paused.check(PauseAction::Liquidate)?;
+        self.collateral_vault_config
+            .paused
+            .check(PauseAction::Liquidate)?;
+
+        require!(debt_amount > 0, LiquidationError::InvalidAmount);
+        require!(
+            self.collateral_vault_config.liquidation_mode == LiquidationMode::StabilityPool,
+            LiquidationError::InvalidLiquidationMode
+        );
 
-        let ltv = (current_debt as u128)
-            .checked_mul(10000)
-            .ok_or(ArithmeticError::ArithmeticOverflow)?
-            .checked_div(collateral_value as u128)
-            .ok_or(ArithmeticError::ArithmeticOverflow)? as u16;
+        // Settle accrued interest and any redistributed debt and collateral into the position
+        self.collateral_vault_config
+            .apply_pending_redistribution(&mut self.position, &self.protocol_config)?;
+
+        let current_debt = self.position.debt_amount;
+        let price = get_guarded_price(
+            &self.price_feed,
+            self.secondary_price_feed.as_deref(),
+            &self.collateral_vault_config,
+        )?
+        .ok_or(OracleError::PriceFrozen)?;
+
+        let collateral_value = price_math::collateral_value(
+            self.position.collateral_amount,
+            price.upper_bound(),
+            price.exponent,
+            self.collateral_mint.decimals,
+        )?;
+
+        let ltv = price_math::ltv(current_debt, collateral_value)?;
+
+        require!(
+            ltv > self.collateral_vault_config.liquidation_threshold as u128,
+            PositionError::InvalidLTV
+        );
 
-        require!(MAX_LTV <= ltv, PositionError::InvalidLTV);
+        let penalty_scale = (BPS_SCALE as u128)
+            .checked_add(self.collateral_vault_config.liquidation_penalty as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
 
-        let collateral_transfer_cpi_accounts = Transfer {
-            from: self.collateral_vault.to_account_info(),
-            to: self.liquidation_rewards_vault.to_account_info(),
-            authority: self.auth.to_account_info(),
+        // The close factor only applies while the collateral still covers debt plus penalty,
+        // otherwise the whole position has to go
+        let is_underwater = collateral_value
+            .checked_mul(BPS_SCALE as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            <= (current_debt as u128)
+                .checked_mul(penalty_scale)
+                .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+        let max_repay = if is_underwater {
+            current_debt
+        } else {
+            (current_debt as u128)
+                .checked_mul(self.collateral_vault_config.close_factor as u128)
+                .ok_or(ArithmeticError::ArithmeticOverflow)?
+                .checked_div(BPS_SCALE as u128)
+                .ok_or(ArithmeticError::ArithmeticOverflow)? as u64
         };
-        let seeds = &[&b"auth"[..], &[self.protocol_config.auth_bump]];
 
-        let signer_seeds = &[&seeds[..]];
+        // A partial liquidation must not leave dust, positions at or below the minimum debt
+        // can only be liquidated in full
+        let repay_amount = match self
+            .collateral_vault_config
+            .cap_debt_reduction(current_debt, debt_amount.min(max_repay))
+        {
+            0 => current_debt,
+            repay_amount => repay_amount,
+        };
 
-        let collateral_transfer_cpi_ctx = CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
-            collateral_transfer_cpi_accounts,
-            signer_seeds,
-        );
+        require!(repay_amount > 0, LiquidationError::InvalidAmount);
 
-        transfer(collateral_transfer_cpi_ctx, self.position.collateral_amount)?;
+        // Collateral worth the repaid debt plus the liquidation penalty
+        let seized_value = (repay_amount as u128)
+            .checked_mul(penalty_scale)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(BPS_SCALE as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)? as u64;
+
+        let collateral_seized = price_math::collateral_amount(
+            seized_value,
+            price.reference_price(),
+            price.exponent,
+            self.collateral_mint.decimals,
+        )?
+        .min(self.position.collateral_amount as u128) as u64;
+
+        // Keeper incentive carved out of the seized collateral
+        let liquidator_reward_amount = (collateral_seized as u128)
+            .checked_mul(self.collateral_vault_config.liquidator_reward as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(BPS_SCALE as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            as u64;
 
-        self.collateral_vault_config.collateral_amount = self
-            .collateral_vault_config
-            .collateral_amount
-            .checked_sub(self.position.collateral_amount)
+        let remaining_collateral = collateral_seized
+            .checked_sub(liquidator_reward_amount)
             .ok_or(ArithmeticError::ArithmeticOverflow)?;
 
-        self.collateral_vault_config.stability_pool_rewards_amount = self
-            .collateral_vault_config
-            .stability_pool_rewards_amount
-            .checked_add(self.position.collateral_amount)
-            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+        // The stability pool absorbs as much debt as its deposits cover, anything beyond
+        // that is redistributed to the other positions of this collateral
+        let pool_debt = (repay_amount as u128)
+            .min(self.protocol_config.total_stake_amount)
+            .min(self.stake_vault.amount as u128) as u64;
 
-        self.collateral_vault_config.gain_summation = self
-            .collateral_vault_config
-            .gain_summation
-            .checked_add(
-                (self.position.collateral_amount as u128)
-                    .checked_mul(self.protocol_config.deposit_depletion_factor as u128)
-                    .ok_or(ArithmeticError::ArithmeticOverflow)?
-                    .checked_div(BPS_SCALE as u128)
-                    .ok_or(ArithmeticError::ArithmeticOverflow)?
-                    .checked_div(self.protocol_config.total_stake_amount)
-                    .ok_or(ArithmeticError::ArithmeticOverflow)?,
-            )
+        let redistributed_debt = repay_amount
+            .checked_sub(pool_debt)
             .ok_or(ArithmeticError::ArithmeticOverflow)?;
 
-        self.protocol_config.deposit_depletion_factor =
-            (self.protocol_config.deposit_depletion_factor as u128)
-                .checked_mul(
-                    (self.protocol_config.total_stake_amount)
-                        .checked_sub(current_debt as u128)
-                        .ok_or(ArithmeticError::ArithmeticOverflow)?,
-                )
-                .ok_or(ArithmeticError::ArithmeticOverflow)?
-                .checked_div(self.protocol_config.total_stake_amount)
-                .ok_or(ArithmeticError::ArithmeticOverflow)? as u16;
+        let pool_collateral = (remaining_collateral as u128)
+            .checked_mul(pool_debt as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(repay_amount as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)? as u64;
 
-        self.protocol_config.total_stake_amount = self
-            .protocol_config
-            .total_stake_amount
-            .checked_sub(current_debt as u128)
+        let redistributed_collateral = remaining_collateral
+            .checked_sub(pool_collateral)
             .ok_or(ArithmeticError::ArithmeticOverflow)?;
 
-        // Calculate current debt with accrued interest
+        let seeds = &[&b"auth"[..], &[self.protocol_config.auth_bump]];
 
-        let accounts = Burn {
-            mint: self.stable_mint.to_account_info(),
-            from: self.stake_vault.to_account_info(),
-            authority: self.auth.to_account_info(),
-        };
+        let signer_seeds = &[&seeds[..]];
+
+        if liquidator_reward_amount > 0 {
+            let reward_transfer_cpi_accounts = Transfer {
+                from: self.collateral_vault.to_account_info(),
+                to: self.liquidator_ata.to_account_info(),
+                authority: self.auth.to_account_info(),
+            };
+
+            let reward_transfer_cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                reward_transfer_cpi_accounts,
+                signer_seeds,
+            );
 
-        let signer_seeds = &[&b"auth"[..], &[self.protocol_config.auth_bump]];
-        let binding = [&signer_seeds[..]];
+            transfer(reward_transfer_cpi_ctx, liquidator_reward_amount)?;
+        }
 
-        let stable_burn_cpi_ctx =
-            CpiContext::new_with_signer(self.token_program.to_account_info(), accounts, &binding);
+        self.collateral_vault_config.collateral_amount = self
+            .collateral_vault_config
+            .collateral_amount
+            .checked_sub(collateral_seized)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
 
-        burn(stable_burn_cpi_ctx, current_debt)?; // Use current_debt with accrued interest
+        if pool_debt > 0 {
+            let collateral_transfer_cpi_accounts = Transfer {
+                from: self.collateral_vault.to_account_info(),
+                to: self.liquidation_rewards_vault.to_account_info(),
+                authority: self.auth.to_account_info(),
+            };
+
+            let collateral_transfer_cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                collateral_transfer_cpi_accounts,
+                signer_seeds,
+            );
+
+            transfer(collateral_transfer_cpi_ctx, pool_collateral)?;
+
+            self.collateral_vault_config
+                .reward_stability_pool(pool_collateral, &self.protocol_config)?;
+
+            self.protocol_config.deplete_stability_pool(pool_debt)?;
+
+            let accounts = Burn {
+                mint: self.stable_mint.to_account_info(),
+                from: self.stake_vault.to_account_info(),
+                authority: self.auth.to_account_info(),
+            };
+
+            let stable_burn_cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                accounts,
+                signer_seeds,
+            );
+
+            burn(stable_burn_cpi_ctx, pool_debt)?;
+
+            self.protocol_config.update_totals(-(pool_debt as i64))?;
+            self.collateral_vault_config.update_debt(
+                -(pool_debt as i64),
+                self.protocol_config.cumulative_interest_rate,
+            )?;
+        }
+
+        // The position stays open while any debt is left
+        self.position.debt_amount = current_debt
+            .checked_sub(repay_amount)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+        self.position.collateral_amount = self
+            .position
+            .collateral_amount
+            .checked_sub(collateral_seized)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
 
-        self.protocol_config.update_totals(-(current_debt as i64))?;
+        if redistributed_debt > 0 {
+            // Redistributed debt is not burned, it stays in total_debt under other positions
+            self.collateral_vault_config.redistribute(
+                redistributed_collateral,
+                redistributed_debt,
+                self.position.collateral_amount,
+                self.protocol_config.cumulative_interest_rate,
+            )?;
+
+            self.position.redistributed_collateral_snapshot = self
+                .collateral_vault_config
+                .redistributed_collateral_per_unit;
+            self.position.redistributed_debt_snapshot =
+                self.collateral_vault_config.redistributed_debt_per_unit;
+        }
+
+        // A partly liquidated position moves to its new place in the list
+        let position_key = self.position.key();
+        let mut positions = PositionList::load(
+            &self.collateral_mint.key(),
+            &[position_key],
+            remaining_accounts,
+        )?;
+        positions.remove(
+            &mut self.collateral_vault_config,
+            position_key,
+            &mut self.position,
+        )?;
+        positions.insert(
+            &mut self.collateral_vault_config,
+            position_key,
+            &mut self.position,
+        )?;
+        positions.exit()?;
+
+        let mut surplus_collateral = 0;
+
+        if self.position.debt_amount == 0 {
+            // Collateral beyond debt plus penalty still belongs to the borrower
+            surplus_collateral = self.position.collateral_amount;
+
+            if surplus_collateral > 0 {
+                let surplus_transfer_cpi_accounts = Transfer {
+                    from: self.collateral_vault.to_account_info(),
+                    to: self.user_ata.to_account_info(),
+                    authority: self.auth.to_account_info(),
+                };
+
+                let surplus_transfer_cpi_ctx = CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    surplus_transfer_cpi_accounts,
+                    signer_seeds,
+                );
+
+                transfer(surplus_transfer_cpi_ctx, surplus_collateral)?;
+
+                self.collateral_vault_config.collateral_amount = self
+                    .collateral_vault_config
+                    .collateral_amount
+                    .checked_sub(surplus_collateral)
+                    .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+                self.position.collateral_amount = 0;
+            }
+
+            self.position.close(self.user.to_account_info())?;
+        }
+
+        emit!(PositionLiquidated {
+            liquidator: self.liquidator.key(),
+            user: self.user.key(),
+            collateral_mint: self.collateral_mint.key(),
+            price: price.reference_price(),
+            price_exponent: price.exponent,
+            debt_repaid: repay_amount,
+            debt_burned: pool_debt,
+            debt_redistributed: redistributed_debt,
+            collateral_seized,
+            liquidator_reward: liquidator_reward_amount,
+            collateral_to_pool: pool_collateral,
+            collateral_redistributed: redistributed_collateral,
+            surplus_collateral,
+            remaining_collateral: self.position.collateral_amount,
+            remaining_debt: self.position.debt_amount,
+            stake_epoch: self.protocol_config.stake_epoch,
+            cumulative_interest_rate: self.protocol_config.cumulative_interest_rate,
+        });
 
         Ok(())
     }
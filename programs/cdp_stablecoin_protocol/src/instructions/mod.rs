@@ -10,6 +10,9 @@ pub use open_position::*;
 pub mod close_position;
 pub use close_position::*;
 
+pub mod adjust_position;
+pub use adjust_position::*;
+
 pub mod update_interest_rate;
 pub use update_interest_rate::*;
 
@@ -24,3 +27,50 @@ pub use liquidate::*;
 
 pub mod claim_stake_reward;
 pub use claim_stake_reward::*;
+
+pub mod redeem;
+pub use redeem::*;
+
+pub mod collect_interest;
+pub use collect_interest::*;
+
+pub mod start_auction;
+pub use start_auction::*;
+
+pub mod bid_auction;
+pub use bid_auction::*;
+
+pub mod reset_auction;
+pub use reset_auction::*;
+
+#[cfg(feature = "mock-oracle")]
+pub mod set_mock_price;
+#[cfg(feature = "mock-oracle")]
+pub use set_mock_price::*;
+
+pub mod update_collateral_oracle;
+pub use update_collateral_oracle::*;
+
+pub mod update_stablecoin_oracle;
+pub use update_stablecoin_oracle::*;
+
+pub mod propose_admin;
+pub use propose_admin::*;
+
+pub mod accept_admin;
+pub use accept_admin::*;
+
+pub mod update_protocol_config;
+pub use update_protocol_config::*;
+
+pub mod set_guardian;
+pub use set_guardian::*;
+
+pub mod set_protocol_pause;
+pub use set_protocol_pause::*;
+
+pub mod set_collateral_pause;
+pub use set_collateral_pause::*;
+
+pub mod set_collateral_debt_limits;
+pub use set_collateral_debt_limits::*;
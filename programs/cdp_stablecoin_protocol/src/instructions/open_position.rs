@@ -1,14 +1,16 @@
-use anchor_lang::{prelude::*, solana_program::native_token::LAMPORTS_PER_SOL};
+use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
     token::{mint_to, transfer, Mint, MintTo, Token, TokenAccount, Transfer},
 };
-use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
 
 use crate::{
-    constants::MAX_LTV,
-    errors::{ArithmeticError, PositionError},
-    state::{CollateralConfig, Position, ProtocolConfig},
+    errors::{ArithmeticError, OracleError, PositionError},
+    events::{MintFeeCharged, PositionOpened},
+    oracle::get_guarded_price,
+    position_list::PositionList,
+    price_math,
+    state::{CollateralConfig, PauseAction, Position, ProtocolConfig},
 };
 
 #[derive(Accounts)]
@@ -66,8 +68,10 @@ pub struct OpenPosition<'info> {
         bump
     )]
     position: Box<Account<'info, Position>>,
-    #[account(owner = pyth_solana_receiver_sdk::ID)]
-    price_feed: Account<'info, PriceUpdateV2>,
+    /// CHECK: Owner and layout are checked by the oracle backend in the config
+    price_feed: UncheckedAccount<'info>,
+    /// CHECK: Only needed when the collateral has a secondary oracle, checked like price_feed
+    secondary_price_feed: Option<UncheckedAccount<'info>>,
     #[account(
         mut,
         seeds = [b"collateral_vault", collateral_mint.key().as_ref()],
@@ -76,46 +80,88 @@ pub struct OpenPosition<'info> {
         bump = collateral_vault_config.vault_bump
     )]
     collateral_vault: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"treasury", stable_mint.key().as_ref()],
+        token::mint = stable_mint,
+        token::authority = auth,
+        bump
+    )]
+    treasury_vault: Box<Account<'info, TokenAccount>>,
     token_program: Program<'info, Token>,
     associated_token_program: Program<'info, AssociatedToken>,
     system_program: Program<'info, System>,
 }
 
 impl<'info> OpenPosition<'info> {
-    pub fn open_position(&mut self, collateral_amount: u64, debt_amount: u64) -> Result<()> {
-        let price_feed = &self.price_feed;
-
-        // let maximum_age: u64 = 30;
+    pub fn open_position(
+        &mut self,
+        collateral_amount: u64,
+        debt_amount: u64,
+        remaining_accounts: &'info [AccountInfo<'info>],
+    ) -> Result<()> {
+        self.protocol_config.paused.check(PauseAction::Open)?;
+        self.collateral_vault_config
+            .paused
+            .check(PauseAction::Open)?;
+
+        let mint_fee = self.protocol_config.calculate_mint_fee(debt_amount)?;
+
+        // The origination fee is owed by the position on top of what the user receives
+        let total_debt_amount = debt_amount
+            .checked_add(mint_fee)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
 
-        let feed_id: [u8; 32] =
-            get_feed_id_from_hex(&self.collateral_vault_config.collateral_price_feed)?;
+        self.collateral_vault_config
+            .check_min_debt(total_debt_amount)?;
 
-        // let price = price_feed.get_price_no_older_than(&Clock::get()?, maximum_age, &feed_id)?;
-        let price = price_feed.get_price_unchecked(&feed_id)?;
+        let price = get_guarded_price(
+            &self.price_feed,
+            self.secondary_price_feed.as_deref(),
+            &self.collateral_vault_config,
+        )?
+        .ok_or(OracleError::PriceFrozen)?;
 
-        let collateral_value = (price.price as u128)
-            .checked_mul(collateral_amount as u128)
-            .ok_or(ArithmeticError::ArithmeticOverflow)?
-            .checked_div(10_u128.pow(price.exponent.abs() as u32))
-            .ok_or(ArithmeticError::ArithmeticOverflow)?
-            .checked_div(LAMPORTS_PER_SOL as u128)
-            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+        let collateral_value = price_math::collateral_value(
+            collateral_amount,
+            price.lower_bound(),
+            price.exponent,
+            self.collateral_mint.decimals,
+        )?;
 
-        let ltv = (debt_amount as u128)
-            .checked_mul(10000)
-            .ok_or(ArithmeticError::ArithmeticOverflow)?
-            .checked_div(collateral_value as u128)
-            .ok_or(ArithmeticError::ArithmeticOverflow)? as u16;
+        let ltv = price_math::ltv(total_debt_amount, collateral_value)?;
 
-        require!(ltv <= MAX_LTV, PositionError::InvalidLTV);
+        require!(
+            ltv <= self.collateral_vault_config.max_ltv as u128,
+            PositionError::InvalidLTV
+        );
 
         self.position.set_inner(Position {
             user: self.user.key(),
             collateral_amount,
-            debt_amount,
+            debt_amount: total_debt_amount,
             prev_cumulative_interest_rate: self.protocol_config.cumulative_interest_rate,
+            redistributed_collateral_snapshot: self
+                .collateral_vault_config
+                .redistributed_collateral_per_unit,
+            redistributed_debt_snapshot: self.collateral_vault_config.redistributed_debt_per_unit,
+            prev_position: None,
+            next_position: None,
         });
 
+        let position_key = self.position.key();
+        let mut positions = PositionList::load(
+            &self.collateral_mint.key(),
+            &[position_key],
+            remaining_accounts,
+        )?;
+        positions.insert(
+            &mut self.collateral_vault_config,
+            position_key,
+            &mut self.position,
+        )?;
+        positions.exit()?;
+
         let collateral_transfer_cpi_accounts = Transfer {
             from: self.user_ata.to_account_info(),
             to: self.collateral_vault.to_account_info(),
@@ -135,7 +181,16 @@ impl<'info> OpenPosition<'info> {
             .checked_add(collateral_amount)
             .ok_or(ArithmeticError::ArithmeticOverflow)?;
 
-        self.protocol_config.update_totals(debt_amount as i64)?;
+        self.protocol_config
+            .update_totals(total_debt_amount as i64)?;
+        self.collateral_vault_config.update_debt(
+            total_debt_amount as i64,
+            self.protocol_config.cumulative_interest_rate,
+        )?;
+
+        self.collateral_vault_config
+            .check_debt_ceiling(self.protocol_config.cumulative_interest_rate)?;
+        self.protocol_config.check_debt_ceiling()?;
 
         let accounts = MintTo {
             mint: self.stable_mint.to_account_info(),
@@ -155,6 +210,38 @@ impl<'info> OpenPosition<'info> {
 
         mint_to(stable_mint_cpi_ctx, debt_amount)?;
 
+        if mint_fee > 0 {
+            let accounts = MintTo {
+                mint: self.stable_mint.to_account_info(),
+                to: self.treasury_vault.to_account_info(),
+                authority: self.auth.to_account_info(),
+            };
+
+            let fee_mint_cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                accounts,
+                signer_seeds,
+            );
+
+            mint_to(fee_mint_cpi_ctx, mint_fee)?;
+
+            emit!(MintFeeCharged {
+                user: self.user.key(),
+                collateral_mint: self.collateral_mint.key(),
+                debt_amount,
+                fee: mint_fee,
+            });
+        }
+
+        emit!(PositionOpened {
+            user: self.user.key(),
+            collateral_mint: self.collateral_mint.key(),
+            collateral_amount,
+            debt_amount: total_debt_amount,
+            mint_fee,
+            cumulative_interest_rate: self.protocol_config.cumulative_interest_rate,
+        });
+
         Ok(())
     }
 }
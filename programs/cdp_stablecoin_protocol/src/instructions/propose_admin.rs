@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+
+use crate::{errors::AdminError, events::AdminProposed, state::ProtocolConfig};
+
+#[derive(Accounts)]
+pub struct ProposeAdmin<'info> {
+    admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = protocol_config.bump,
+        has_one = admin @ AdminError::Unauthorized,
+    )]
+    protocol_config: Account<'info, ProtocolConfig>,
+}
+
+impl<'info> ProposeAdmin<'info> {
+    // First step of an admin transfer, the current admin stays in charge until accepted.
+    // Proposing again replaces the pending admin
+    pub fn propose_admin(&mut self, new_admin: Pubkey) -> Result<()> {
+        self.protocol_config.pending_admin = Some(new_admin);
+
+        emit!(AdminProposed {
+            admin: self.admin.key(),
+            pending_admin: new_admin,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{burn, transfer, Burn, Mint, Token, TokenAccount, Transfer},
+};
+
+use crate::{
+    errors::{ArithmeticError, OracleError, RedemptionError},
+    events::PositionRedeemed,
+    oracle::get_guarded_price,
+    position_list::PositionList,
+    price_math,
+    state::{CollateralConfig, PauseAction, ProtocolConfig},
+};
+
+#[derive(Accounts)]
+pub struct Redeem<'info> {
+    #[account(mut)]
+    redeemer: Signer<'info>,
+
+    collateral_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        address = protocol_config.stable_mint,
+        mint::decimals = 6,
+        mint::authority = auth,
+    )]
+    stable_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = protocol_config.bump
+    )]
+    protocol_config: Account<'info, ProtocolConfig>,
+    /// CHECK: This is an auth acc for the vault
+    #[account(
+        seeds = [b"auth"],
+        bump = protocol_config.auth_bump
+    )]
+    auth: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = redeemer,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = redeemer,
+    )]
+    redeemer_ata: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = stable_mint,
+        associated_token::authority = redeemer,
+    )]
+    redeemer_stable_ata: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"collateral", collateral_mint.key().as_ref()],
+        constraint = collateral_vault_config.mint == collateral_mint.key(),
+        bump = collateral_vault_config.bump
+    )]
+    collateral_vault_config: Account<'info, CollateralConfig>,
+    /// CHECK: Owner and layout are checked by the oracle backend in the config
+    price_feed: UncheckedAccount<'info>,
+    /// CHECK: Only needed when the collateral has a secondary oracle, checked like price_feed
+    secondary_price_feed: Option<UncheckedAccount<'info>>,
+    #[account(
+        mut,
+        seeds = [b"collateral_vault", collateral_mint.key().as_ref()],
+        token::mint = collateral_mint,
+        token::authority = auth,
+        bump = collateral_vault_config.vault_bump
+    )]
+    collateral_vault: Box<Account<'info, TokenAccount>>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> Redeem<'info> {
+    // Debt is redeemed from the riskiest position of the collateral onwards, following the
+    // position list, each position in full before the next. The redeemed positions are
+    // passed as remaining accounts in list order, followed by the neighbours of the place
+    // the last one moves to when it is only redeemed in part.
+    pub fn redeem(
+        &mut self,
+        amount: u64,
+        remaining_accounts: &'info [AccountInfo<'info>],
+    ) -> Result<()> {
+        self.protocol_config.paused.check(PauseAction::Redeem)?;
+        self.collateral_vault_config
+            .paused
+            .check(PauseAction::Redeem)?;
+
+        require!(amount > 0, RedemptionError::InvalidAmount);
+        let price = get_guarded_price(
+            &self.price_feed,
+            self.secondary_price_feed.as_deref(),
+            &self.collateral_vault_config,
+        )?
+        .ok_or(OracleError::PriceFrozen)?;
+
+        let collateral_mint = self.collateral_mint.key();
+
+        let mut positions = PositionList::load(&collateral_mint, &[], remaining_accounts)?;
+
+        let mut remainder = amount;
+        let mut collateral_out: u64 = 0;
+        let mut cursor = self.collateral_vault_config.riskiest_position;
+
+        while remainder > 0 {
+            let key = cursor.ok_or(RedemptionError::InsufficientDebtToRedeem)?;
+            let mut position = positions.get(key)?.clone();
+
+            self.collateral_vault_config
+                .apply_pending_redistribution(&mut position, &self.protocol_config)?;
+
+            let current_debt = position.debt_amount;
+
+            // Only the last position is redeemed in part, and only down to the minimum debt
+            let redeemed_debt = remainder.min(current_debt);
+            require!(
+                self.collateral_vault_config
+                    .cap_debt_reduction(current_debt, redeemed_debt)
+                    == redeemed_debt,
+                RedemptionError::DebtBelowMinimum
+            );
+
+            // Collateral worth the redeemed debt at oracle price, less the redemption fee
+            // which stays with the position being redeemed against
+            let collateral_redeemed = price_math::redeemed_collateral(
+                redeemed_debt,
+                price.upper_bound(),
+                price.exponent,
+                self.collateral_mint.decimals,
+                self.protocol_config.redemption_fee,
+            )?;
+
+            position.collateral_amount = position
+                .collateral_amount
+                .checked_sub(collateral_redeemed)
+                .ok_or(RedemptionError::InsufficientCollateral)?;
+
+            position.debt_amount = current_debt
+                .checked_sub(redeemed_debt)
+                .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+            remainder = remainder
+                .checked_sub(redeemed_debt)
+                .ok_or(ArithmeticError::ArithmeticOverflow)?;
+            cursor = position.next_position;
+
+            // Fully redeemed positions leave the list, a partly redeemed one moves down it
+            positions.remove(&mut self.collateral_vault_config, key, &mut position)?;
+            positions.insert(&mut self.collateral_vault_config, key, &mut position)?;
+
+            emit!(PositionRedeemed {
+                redeemer: self.redeemer.key(),
+                user: position.user,
+                collateral_mint,
+                price: price.upper_bound(),
+                price_exponent: price.exponent,
+                debt_redeemed: redeemed_debt,
+                collateral_redeemed,
+                remaining_collateral: position.collateral_amount,
+                remaining_debt: position.debt_amount,
+                cumulative_interest_rate: self.protocol_config.cumulative_interest_rate,
+            });
+
+            *positions.get_mut(key)? = position;
+
+            collateral_out = collateral_out
+                .checked_add(collateral_redeemed)
+                .ok_or(ArithmeticError::ArithmeticOverflow)?;
+        }
+
+        positions.exit()?;
+
+        let accounts = Burn {
+            mint: self.stable_mint.to_account_info(),
+            from: self.redeemer_stable_ata.to_account_info(),
+            authority: self.redeemer.to_account_info(),
+        };
+
+        let stable_burn_cpi_ctx = CpiContext::new(self.token_program.to_account_info(), accounts);
+
+        burn(stable_burn_cpi_ctx, amount)?;
+
+        self.protocol_config.update_totals(-(amount as i64))?;
+        self.collateral_vault_config.update_debt(
+            -(amount as i64),
+            self.protocol_config.cumulative_interest_rate,
+        )?;
+
+        let collateral_transfer_cpi_accounts = Transfer {
+            from: self.collateral_vault.to_account_info(),
+            to: self.redeemer_ata.to_account_info(),
+            authority: self.auth.to_account_info(),
+        };
+        let seeds = &[&b"auth"[..], &[self.protocol_config.auth_bump]];
+
+        let signer_seeds = &[&seeds[..]];
+
+        let collateral_transfer_cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            collateral_transfer_cpi_accounts,
+            signer_seeds,
+        );
+
+        transfer(collateral_transfer_cpi_ctx, collateral_out)?;
+
+        self.collateral_vault_config.collateral_amount = self
+            .collateral_vault_config
+            .collateral_amount
+            .checked_sub(collateral_out)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::{
+    errors::{LiquidationError, OracleError},
+    events::AuctionReset,
+    oracle::get_guarded_price,
+    state::{Auction, CollateralConfig, PauseAction, ProtocolConfig},
+};
+
+#[derive(Accounts)]
+pub struct ResetAuction<'info> {
+    user: Signer<'info>,
+
+    collateral_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = protocol_config.bump
+    )]
+    protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"collateral", collateral_mint.key().as_ref()],
+        bump = collateral_vault_config.bump
+    )]
+    collateral_vault_config: Account<'info, CollateralConfig>,
+    #[account(
+        mut,
+        seeds = [b"auction", collateral_mint.key().as_ref(), auction.id.to_le_bytes().as_ref()],
+        bump = auction.bump
+    )]
+    auction: Account<'info, Auction>,
+
+    /// CHECK: Owner and layout are checked by the oracle backend in the config
+    price_feed: UncheckedAccount<'info>,
+    /// CHECK: Only needed when the collateral has a secondary oracle, checked like price_feed
+    secondary_price_feed: Option<UncheckedAccount<'info>>,
+}
+
+impl<'info> ResetAuction<'info> {
+    // An auction left at its floor is priced off a stale oracle reading, so once it has
+    // run its course anyone can restart the decay from the current price
+    pub fn reset_auction(&mut self) -> Result<()> {
+        self.protocol_config.paused.check(PauseAction::Liquidate)?;
+        self.collateral_vault_config
+            .paused
+            .check(PauseAction::Liquidate)?;
+
+        require!(
+            self.auction.collateral_amount > 0,
+            LiquidationError::AuctionFinished
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(
+            now >= self
+                .auction
+                .start_time
+                .saturating_add(self.auction.duration),
+            LiquidationError::AuctionNotExpired
+        );
+
+        let price = get_guarded_price(
+            &self.price_feed,
+            self.secondary_price_feed.as_deref(),
+            &self.collateral_vault_config,
+        )?
+        .ok_or(OracleError::PriceFrozen)?;
+
+        let auction_params = self.collateral_vault_config.auction_params;
+        let (start_price, floor_price) = auction_params.price_range(price.reference_price())?;
+
+        // Settle interest so the debt keeps accruing from the restart
+        self.auction.debt_amount = self
+            .auction
+            .calculate_current_debt(self.protocol_config.cumulative_interest_rate)?;
+        self.auction.prev_cumulative_interest_rate = self.protocol_config.cumulative_interest_rate;
+
+        self.auction.start_price = start_price;
+        self.auction.floor_price = floor_price;
+        self.auction.price_exponent = price.exponent;
+        self.auction.start_time = now;
+        self.auction.duration = auction_params.duration;
+
+        emit!(AuctionReset {
+            user: self.user.key(),
+            collateral_mint: self.collateral_mint.key(),
+            auction_id: self.auction.id,
+            collateral_amount: self.auction.collateral_amount,
+            debt_amount: self.auction.debt_amount,
+            start_price,
+            floor_price,
+            price_exponent: price.exponent,
+            cumulative_interest_rate: self.protocol_config.cumulative_interest_rate,
+        });
+
+        Ok(())
+    }
+}
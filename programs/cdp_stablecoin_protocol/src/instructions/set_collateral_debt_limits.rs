@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::{
+    errors::AdminError,
+    events::CollateralDebtLimitsUpdated,
+    state::{CollateralConfig, ProtocolConfig},
+};
+
+#[derive(Accounts)]
+pub struct SetCollateralDebtLimits<'info> {
+    admin: Signer<'info>,
+
+    collateral_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = protocol_config.bump,
+        has_one = admin @ AdminError::Unauthorized,
+    )]
+    protocol_config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        seeds = [b"collateral", collateral_mint.key().as_ref()],
+        bump = collateral_vault_config.bump
+    )]
+    collateral_vault_config: Account<'info, CollateralConfig>,
+}
+
+impl<'info> SetCollateralDebtLimits<'info> {
+    // Lowering the ceiling below the outstanding debt only stops new mints against this
+    // collateral, raising the minimum only applies to positions when their debt next changes
+    pub fn set_collateral_debt_limits(&mut self, debt_ceiling: u64, min_debt: u64) -> Result<()> {
+        self.collateral_vault_config.debt_ceiling = debt_ceiling;
+        self.collateral_vault_config.min_debt = min_debt;
+
+        emit!(CollateralDebtLimitsUpdated {
+            collateral_mint: self.collateral_mint.key(),
+            debt_ceiling,
+            min_debt,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::{
+    errors::PauseError,
+    events::PauseFlagsUpdated,
+    state::{CollateralConfig, PauseFlags, ProtocolConfig},
+};
+
+#[derive(Accounts)]
+pub struct SetCollateralPause<'info> {
+    authority: Signer<'info>,
+
+    collateral_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = protocol_config.bump,
+        constraint = authority.key() == protocol_config.guardian
+            || authority.key() == protocol_config.admin @ PauseError::NotGuardian,
+    )]
+    protocol_config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        seeds = [b"collateral", collateral_mint.key().as_ref()],
+        bump = collateral_vault_config.bump
+    )]
+    collateral_vault_config: Account<'info, CollateralConfig>,
+}
+
+impl<'info> SetCollateralPause<'info> {
+    pub fn set_collateral_pause(&mut self, paused: PauseFlags) -> Result<()> {
+        let is_admin = self.authority.key() == self.protocol_config.admin;
+
+        self.collateral_vault_config
+            .paused
+            .update(paused, is_admin)?;
+
+        emit!(PauseFlagsUpdated {
+            authority: self.authority.key(),
+            collateral_mint: Some(self.collateral_mint.key()),
+            paused,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+use crate::{errors::AdminError, events::GuardianSet, state::ProtocolConfig};
+
+#[derive(Accounts)]
+pub struct SetGuardian<'info> {
+    admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = protocol_config.bump,
+        has_one = admin @ AdminError::Unauthorized,
+    )]
+    protocol_config: Account<'info, ProtocolConfig>,
+}
+
+impl<'info> SetGuardian<'info> {
+    pub fn set_guardian(&mut self, guardian: Pubkey) -> Result<()> {
+        self.protocol_config.guardian = guardian;
+
+        emit!(GuardianSet { guardian });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+
+use crate::{errors::AdminError, program::CdpStablecoinProtocol, state::MockPrice};
+
+#[derive(Accounts)]
+#[instruction(feed_id: [u8; 32])]
+pub struct SetMockPrice<'info> {
+    #[account(mut)]
+    admin: Signer<'info>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    program: Program<'info, CdpStablecoinProtocol>,
+    // Set by the upgrade authority rather than the config admin, so the stablecoin feed
+    // can be priced before initialize_protocol_config reads it
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ AdminError::Unauthorized
+    )]
+    program_data: Account<'info, ProgramData>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + MockPrice::INIT_SPACE,
+        seeds = [b"mock_price", feed_id.as_ref()],
+        bump,
+    )]
+    mock_price: Account<'info, MockPrice>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> SetMockPrice<'info> {
+    pub fn set_mock_price(
+        &mut self,
+        feed_id: [u8; 32],
+        price: i64,
+        ema_price: i64,
+        conf: u64,
+        exponent: i32,
+        bumps: &SetMockPriceBumps,
+    ) -> Result<()> {
+        self.mock_price.set_inner(MockPrice {
+            feed_id,
+            price,
+            ema_price,
+            conf,
+            exponent,
+            publish_time: Clock::get()?.unix_timestamp,
+            bump: bumps.mock_price,
+        });
+
+        Ok(())
+    }
+}
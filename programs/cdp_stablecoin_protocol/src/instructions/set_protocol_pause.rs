@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::PauseError,
+    events::PauseFlagsUpdated,
+    state::{PauseFlags, ProtocolConfig},
+};
+
+#[derive(Accounts)]
+pub struct SetProtocolPause<'info> {
+    authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = protocol_config.bump,
+        constraint = authority.key() == protocol_config.guardian
+            || authority.key() == protocol_config.admin @ PauseError::NotGuardian,
+    )]
+    protocol_config: Account<'info, ProtocolConfig>,
+}
+
+impl<'info> SetProtocolPause<'info> {
+    // Pause flags here apply to every collateral
+    pub fn set_protocol_pause(&mut self, paused: PauseFlags) -> Result<()> {
+        let is_admin = self.authority.key() == self.protocol_config.admin;
+
+        self.protocol_config.paused.update(paused, is_admin)?;
+
+        emit!(PauseFlagsUpdated {
+            authority: self.authority.key(),
+            collateral_mint: None,
+            paused,
+        });
+
+        Ok(())
+    }
+}
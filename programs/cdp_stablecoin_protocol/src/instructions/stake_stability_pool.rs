@@ -4,7 +4,10 @@ use anchor_spl::{
     token::{transfer, Mint, Token, TokenAccount, Transfer},
 };
 
-use crate::state::{CollateralConfig, ProtocolConfig, StakeAccount};
+use crate::{
+    events::Staked,
+    state::{CollateralConfig, PauseAction, ProtocolConfig, StakeAccount},
+};
 
 #[derive(Accounts)]
 pub struct Stake<'info> {
@@ -68,13 +71,20 @@ impl<'info> Stake<'info> {
             amount: 0,
             init_deposit_depletion_factor: self.protocol_config.deposit_depletion_factor,
             init_gain_summation: self.collateral_vault_config.gain_summation,
+            init_interest_gain_summation: self.protocol_config.interest_gain_summation,
             last_staked: Clock::get()?.unix_timestamp,
+            epoch: self.protocol_config.stake_epoch,
             bump: bumps.stake_account,
         });
 
         Ok(())
     }
     pub fn deposit_tokens(&mut self, amount: u64) -> Result<()> {
+        self.protocol_config.paused.check(PauseAction::Stake)?;
+        self.collateral_vault_config
+            .paused
+            .check(PauseAction::Stake)?;
+
         // Transfer tokens
         let cpi_program = self.token_program.to_account_info();
 
@@ -98,6 +108,14 @@ impl<'info> Stake<'info> {
 
         self.protocol_config.total_stake_amount += amount as u128;
 
+        emit!(Staked {
+            user: self.user.key(),
+            collateral_mint: self.collateral_vault_config.mint,
+            amount,
+            stake_amount: self.stake_account.amount,
+            total_stake_amount: self.protocol_config.total_stake_amount,
+        });
+
         Ok(())
     }
 }
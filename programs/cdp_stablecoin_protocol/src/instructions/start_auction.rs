@@ -0,0 +1,165 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::{
+    errors::{ArithmeticError, LiquidationError, OracleError, PositionError},
+    events::AuctionStarted,
+    oracle::get_guarded_price,
+    position_list::PositionList,
+    price_math,
+    state::{Auction, CollateralConfig, LiquidationMode, PauseAction, Position, ProtocolConfig},
+};
+
+#[derive(Accounts)]
+pub struct StartAuction<'info> {
+    #[account(mut)]
+    keeper: Signer<'info>,
+
+    #[account(mut)]
+    user: SystemAccount<'info>,
+
+    collateral_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = protocol_config.bump
+    )]
+    protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"collateral", collateral_mint.key().as_ref()],
+        bump = collateral_vault_config.bump
+    )]
+    collateral_vault_config: Account<'info, CollateralConfig>,
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"position", user.key().as_ref(), collateral_mint.key().as_ref()],
+        bump,
+    )]
+    position: Account<'info, Position>,
+    #[account(
+        init,
+        payer = keeper,
+        space = 8 + Auction::INIT_SPACE,
+        seeds = [
+            b"auction",
+            collateral_mint.key().as_ref(),
+            collateral_vault_config.auction_count.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    auction: Account<'info, Auction>,
+
+    /// CHECK: Owner and layout are checked by the oracle backend in the config
+    price_feed: UncheckedAccount<'info>,
+    /// CHECK: Only needed when the collateral has a secondary oracle, checked like price_feed
+    secondary_price_feed: Option<UncheckedAccount<'info>>,
+
+    system_program: Program<'info, System>,
+}
+
+impl<'info> StartAuction<'info> {
+    // Move an unhealthy position's collateral and debt into a Dutch auction
+    pub fn start_auction(
+        &mut self,
+        bumps: &StartAuctionBumps,
+        remaining_accounts: &'info [AccountInfo<'info>],
+    ) -> Result<()> {
+        self.protocol_config.paused.check(PauseAction::Liquidate)?;
+        self.collateral_vault_config
+            .paused
+            .check(PauseAction::Liquidate)?;
+
+        require!(
+            self.collateral_vault_config.liquidation_mode == LiquidationMode::Auction,
+            LiquidationError::InvalidLiquidationMode
+        );
+
+        self.collateral_vault_config
+            .apply_pending_redistribution(&mut self.position, &self.protocol_config)?;
+
+        let current_debt = self.position.debt_amount;
+        let price = get_guarded_price(
+            &self.price_feed,
+            self.secondary_price_feed.as_deref(),
+            &self.collateral_vault_config,
+        )?
+        .ok_or(OracleError::PriceFrozen)?;
+
+        let collateral_value = price_math::collateral_value(
+            self.position.collateral_amount,
+            price.upper_bound(),
+            price.exponent,
+            self.collateral_mint.decimals,
+        )?;
+
+        let ltv = price_math::ltv(current_debt, collateral_value)?;
+
+        require!(
+            ltv > self.collateral_vault_config.liquidation_threshold as u128,
+            PositionError::InvalidLTV
+        );
+
+        let auction_params = self.collateral_vault_config.auction_params;
+        let (start_price, floor_price) = auction_params.price_range(price.reference_price())?;
+
+        self.auction.set_inner(Auction {
+            id: self.collateral_vault_config.auction_count,
+            owner: self.user.key(),
+            keeper: self.keeper.key(),
+            collateral_mint: self.collateral_mint.key(),
+            collateral_amount: self.position.collateral_amount,
+            debt_amount: current_debt,
+            prev_cumulative_interest_rate: self.protocol_config.cumulative_interest_rate,
+            start_price,
+            floor_price,
+            price_exponent: price.exponent,
+            start_time: Clock::get()?.unix_timestamp,
+            duration: auction_params.duration,
+            bump: bumps.auction,
+        });
+
+        let position_key = self.position.key();
+        let mut positions = PositionList::load(
+            &self.collateral_mint.key(),
+            &[position_key],
+            remaining_accounts,
+        )?;
+        positions.remove(
+            &mut self.collateral_vault_config,
+            position_key,
+            &mut self.position,
+        )?;
+        positions.exit()?;
+
+        // Auctioned collateral stays in the vault but no longer backs any position
+        self.collateral_vault_config.collateral_amount = self
+            .collateral_vault_config
+            .collateral_amount
+            .checked_sub(self.position.collateral_amount)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+        self.collateral_vault_config.auction_count = self
+            .collateral_vault_config
+            .auction_count
+            .checked_add(1)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+        emit!(AuctionStarted {
+            keeper: self.keeper.key(),
+            user: self.user.key(),
+            collateral_mint: self.collateral_mint.key(),
+            auction_id: self.auction.id,
+            collateral_amount: self.auction.collateral_amount,
+            debt_amount: current_debt,
+            start_price,
+            floor_price,
+            price_exponent: price.exponent,
+            cumulative_interest_rate: self.protocol_config.cumulative_interest_rate,
+        });
+
+        Ok(())
+    }
+}
@@ -4,7 +4,11 @@ use anchor_spl::{
     token::{transfer, Mint, Token, TokenAccount, Transfer},
 };
 
-use crate::state::{CollateralConfig, ProtocolConfig, StakeAccount};
+use crate::{
+    errors::{ArithmeticError, StakeError},
+    events::Unstaked,
+    state::{CollateralConfig, PauseAction, ProtocolConfig, StakeAccount},
+};
 
 #[derive(Accounts)]
 pub struct UnStake<'info> {
@@ -47,6 +51,11 @@ pub struct UnStake<'info> {
         bump = collateral_vault_config.bump
     )]
     collateral_vault_config: Box<Account<'info, CollateralConfig>>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = protocol_config.bump
+    )]
     protocol_config: Account<'info, ProtocolConfig>,
     system_program: Program<'info, System>,
     token_program: Program<'info, Token>,
@@ -55,6 +64,28 @@ pub struct UnStake<'info> {
 
 impl<'info> UnStake<'info> {
     pub fn withdraw_tokens(&mut self, bumps: &UnStakeBumps) -> Result<()> {
+        self.protocol_config.paused.check(PauseAction::Unstake)?;
+        self.collateral_vault_config
+            .paused
+            .check(PauseAction::Unstake)?;
+
+        // The stake account is closed, so gains left unclaimed would be lost
+        let collateral_gain = self
+            .stake_account
+            .collateral_gain(&self.collateral_vault_config)?;
+        let interest_gain = self.stake_account.interest_gain(&self.protocol_config)?;
+
+        require!(
+            collateral_gain == 0 && interest_gain == 0,
+            StakeError::UnclaimedRewards
+        );
+
+        // Only what is left of the deposit after the liquidations it absorbed, the rest
+        // of the vault belongs to the other depositors
+        let amount = self
+            .stake_account
+            .compounded_amount(&self.protocol_config)?;
+
         // Transfer tokens
         let cpi_program = self.token_program.to_account_info();
 
@@ -68,7 +99,13 @@ impl<'info> UnStake<'info> {
 
         let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, &binding);
 
-        transfer(cpi_ctx, self.stake_vault.amount)?;
+        transfer(cpi_ctx, amount)?;
+
+        self.protocol_config.total_stake_amount = self
+            .protocol_config
+            .total_stake_amount
+            .checked_sub(amount as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
 
         let current_timestamp = Clock::get()?.unix_timestamp;
 
@@ -78,6 +115,13 @@ impl<'info> UnStake<'info> {
         // Update staked amount
         self.stake_account.amount = 0;
 
+        emit!(Unstaked {
+            user: self.user.key(),
+            collateral_mint: self.collateral_vault_config.mint,
+            amount,
+            total_stake_amount: self.protocol_config.total_stake_amount,
+        });
+
         Ok(())
     }
 }
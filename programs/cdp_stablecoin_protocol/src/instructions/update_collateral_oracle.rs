@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::{
+    errors::{AdminError, OracleError},
+    events::CollateralOracleUpdated,
+    oracle::{get_guarded_price, parse_feed_id},
+    state::{CollateralConfig, OracleConfig, ProtocolConfig, SecondaryOracle},
+};
+
+#[derive(Accounts)]
+pub struct UpdateCollateralOracle<'info> {
+    admin: Signer<'info>,
+
+    collateral_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = protocol_config.bump,
+        has_one = admin @ AdminError::Unauthorized,
+    )]
+    protocol_config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        seeds = [b"collateral", collateral_mint.key().as_ref()],
+        bump = collateral_vault_config.bump
+    )]
+    collateral_vault_config: Account<'info, CollateralConfig>,
+    /// CHECK: Owner and layout are checked by the oracle backend in the new config
+    price_feed: UncheckedAccount<'info>,
+    /// CHECK: Only needed when the new config has a secondary oracle, checked like price_feed
+    secondary_price_feed: Option<UncheckedAccount<'info>>,
+}
+
+impl<'info> UpdateCollateralOracle<'info> {
+    // Rotate the price feed of a collateral, e.g. after a feed id typo or a backend change
+    pub fn update_collateral_oracle(
+        &mut self,
+        collateral_price_feed: String,
+        collateral_oracle: OracleConfig,
+        secondary_oracle: Option<SecondaryOracle>,
+    ) -> Result<()> {
+        collateral_oracle.validate()?;
+        if let Some(secondary_oracle) = secondary_oracle {
+            secondary_oracle.validate()?;
+        }
+
+        self.collateral_vault_config.collateral_price_feed = parse_feed_id(&collateral_price_feed)?;
+        self.collateral_vault_config.collateral_oracle = collateral_oracle;
+        self.collateral_vault_config.secondary_oracle = secondary_oracle;
+
+        // A feed that cannot be read now would freeze every position of the collateral
+        get_guarded_price(
+            &self.price_feed,
+            self.secondary_price_feed.as_deref(),
+            &self.collateral_vault_config,
+        )?
+        .ok_or(OracleError::PriceFrozen)?;
+
+        emit!(CollateralOracleUpdated {
+            collateral_mint: self.collateral_mint.key(),
+            collateral_price_feed: self.collateral_vault_config.collateral_price_feed,
+            collateral_oracle,
+            secondary_oracle,
+        });
+
+        Ok(())
+    }
+}
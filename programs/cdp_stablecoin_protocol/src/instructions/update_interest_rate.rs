@@ -1,10 +1,11 @@
 use crate::{
     constants::{BPS_SCALE, INTEREST_SCALE, MAX_INTEREST_RATE, MIN_INTEREST_RATE, YEAR_IN_SECONDS},
     errors::ArithmeticError,
-    state::ProtocolConfig,
+    events::InterestRateUpdated,
+    oracle::get_price,
+    state::{PauseAction, ProtocolConfig},
 };
 use anchor_lang::prelude::*;
-use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
 
 #[derive(Accounts)]
 pub struct UpdateInterestRate<'info> {
@@ -17,8 +18,8 @@ pub struct UpdateInterestRate<'info> {
     )]
     pub protocol_config: Account<'info, ProtocolConfig>,
 
-    #[account(owner = pyth_solana_receiver_sdk::ID)]
-    pub price_feed: Account<'info, PriceUpdateV2>,
+    /// CHECK: Owner and layout are checked by the oracle backend in the config
+    pub price_feed: UncheckedAccount<'info>,
 }
 
 impl<'info> UpdateInterestRate<'info> {
@@ -71,7 +72,7 @@ impl<'info> UpdateInterestRate<'info> {
         sigma_bps: u16,
     ) -> Result<u128> {
         // Calculate price deviation from peg
-        let peg = 10_i128.pow(stablecoin_exponent.abs() as u32);
+        let peg = 10_i128.pow(stablecoin_exponent.unsigned_abs());
 
         let price_deviation: i128 = peg
             .checked_sub(stablecoin_price as i128)
@@ -84,14 +85,14 @@ impl<'info> UpdateInterestRate<'info> {
             .checked_mul(bps_scale_i128)
             .ok_or(ArithmeticError::ArithmeticOverflow)?;
 
-        x = x / sigma_bps as i128;
+        x /= sigma_bps as i128;
 
         // convert exponent from price scale to interest scale
         x = x
             .checked_mul(INTEREST_SCALE as i128)
             .ok_or(ArithmeticError::ArithmeticOverflow)?;
 
-        x = x / peg;
+        x /= peg;
 
         // Calculate rate = base_rate * e^x
         let exp_result = Self::exponential_approximation(x, INTEREST_SCALE)?;
@@ -150,30 +151,44 @@ impl<'info> UpdateInterestRate<'info> {
 
     // Main function to update protocol interest rate
     pub fn update_interest_rate(&mut self) -> Result<()> {
+        self.protocol_config
+            .paused
+            .check(PauseAction::InterestUpdate)?;
+
+        Self::accrue_interest(&mut self.protocol_config, &self.price_feed)
+    }
+
+    // Accrue interest since the last update at the current rate parameters, also used to
+    // settle before those parameters change
+    pub fn accrue_interest(
+        protocol_config: &mut ProtocolConfig,
+        price_feed: &AccountInfo,
+    ) -> Result<()> {
         // Calculate time elapsed
         let current_timestamp = Clock::get()?.unix_timestamp;
-        let time_elapsed =
-            (current_timestamp - self.protocol_config.last_interest_rate_update) as u64;
+        let time_elapsed = (current_timestamp - protocol_config.last_interest_rate_update) as u64;
 
-        match time_elapsed {
-            0 => return Ok(()),
-            _ => {}
-        };
+        if time_elapsed == 0 {
+            return Ok(());
+        }
 
         // Get current stablecoin price
-        let price_feed = &self.price_feed;
-
-        let feed_id: [u8; 32] = get_feed_id_from_hex(&self.protocol_config.stablecoin_price_feed)?;
+        let stablecoin_price = get_price(
+            price_feed,
+            &protocol_config.stablecoin_price_feed,
+            &protocol_config.stablecoin_oracle,
+        )?;
 
-        let stablecoin_price = price_feed.get_price_unchecked(&feed_id)?;
+        // In conservative mode a short spike away from the peg is ignored
+        let peg = 10_i64.pow(stablecoin_price.exponent.unsigned_abs());
 
         // Calculate yearly interest rate
 
         let new_interest_rate_yearly = Self::calculate_interest_rate(
-            stablecoin_price.price,
+            stablecoin_price.closest_to(peg),
             stablecoin_price.exponent,
-            self.protocol_config.base_rate,
-            self.protocol_config.sigma,
+            protocol_config.base_rate,
+            protocol_config.sigma,
         )?;
 
         // Calculate compound interest
@@ -184,18 +199,45 @@ impl<'info> UpdateInterestRate<'info> {
 
         // Update protocol state
 
-        self.protocol_config.cumulative_interest_rate =
-            (self.protocol_config.cumulative_interest_rate)
-                .checked_mul(compounded_interest_rate)
-                .ok_or(ArithmeticError::ArithmeticOverflow)?
-                / INTEREST_SCALE;
+        protocol_config.cumulative_interest_rate = (protocol_config.cumulative_interest_rate)
+            .checked_mul(compounded_interest_rate)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            / INTEREST_SCALE;
 
-        self.protocol_config.total_debt = (self.protocol_config.total_debt as u128)
+        let previous_total_debt = protocol_config.total_debt;
+
+        protocol_config.total_debt = protocol_config
+            .total_debt
             .checked_mul(compounded_interest_rate)
             .ok_or(ArithmeticError::ArithmeticOverflow)?
             / INTEREST_SCALE;
 
-        self.protocol_config.last_interest_rate_update = current_timestamp;
+        // Interest is owed by borrowers from now on but only minted on collect_interest
+        protocol_config.accrued_interest = protocol_config
+            .accrued_interest
+            .checked_add(
+                protocol_config
+                    .total_debt
+                    .checked_sub(previous_total_debt)
+                    .ok_or(ArithmeticError::ArithmeticOverflow)?,
+            )
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+        protocol_config.last_interest_rate_update = current_timestamp;
+
+        let old_interest_rate = protocol_config.interest_rate;
+        protocol_config.interest_rate = new_interest_rate_yearly;
+
+        emit!(InterestRateUpdated {
+            old_rate: old_interest_rate,
+            new_rate: new_interest_rate_yearly,
+            cumulative_interest_rate: protocol_config.cumulative_interest_rate,
+            stablecoin_price: stablecoin_price.closest_to(peg),
+            price_exponent: stablecoin_price.exponent,
+            time_elapsed,
+            total_debt: protocol_config.total_debt,
+            accrued_interest: protocol_config.accrued_interest,
+        });
 
         Ok(())
     }
@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AdminError, events::ProtocolConfigUpdated, instructions::UpdateInterestRate,
+    state::ProtocolConfig,
+};
+
+#[derive(Accounts)]
+pub struct UpdateProtocolConfig<'info> {
+    admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = protocol_config.bump,
+        has_one = admin @ AdminError::Unauthorized,
+    )]
+    protocol_config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: Owner and layout are checked by the oracle backend in the config
+    price_feed: UncheckedAccount<'info>,
+}
+
+impl<'info> UpdateProtocolConfig<'info> {
+    // Retune fees, the rate controller and the global debt ceiling, every field is validated like at initialisation
+    pub fn update_protocol_config(
+        &mut self,
+        protocol_fee: u16,
+        redemption_fee: u16,
+        mint_fee: u16,
+        base_rate: u16,
+        sigma: u16,
+        debt_ceiling: u64,
+    ) -> Result<()> {
+        ProtocolConfig::validate_parameters(
+            protocol_fee,
+            redemption_fee,
+            mint_fee,
+            base_rate,
+            sigma,
+        )?;
+
+        // Interest up to now accrues under the old base rate and sigma. Pausing interest
+        // updates only stops the crank, the time since the last update is still owed, so
+        // it is settled here too rather than charged later at the new rate
+        UpdateInterestRate::accrue_interest(&mut self.protocol_config, &self.price_feed)?;
+
+        self.protocol_config.protocol_fee = protocol_fee;
+        self.protocol_config.redemption_fee = redemption_fee;
+        self.protocol_config.mint_fee = mint_fee;
+        self.protocol_config.base_rate = base_rate;
+        self.protocol_config.sigma = sigma;
+        // Lowering it below the outstanding debt only stops new mints
+        self.protocol_config.debt_ceiling = debt_ceiling;
+
+        emit!(ProtocolConfigUpdated {
+            admin: self.admin.key(),
+            protocol_fee,
+            redemption_fee,
+            mint_fee,
+            base_rate,
+            sigma,
+            debt_ceiling,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AdminError,
+    events::StablecoinOracleUpdated,
+    oracle::{get_price, parse_feed_id},
+    state::{OracleConfig, ProtocolConfig},
+};
+
+#[derive(Accounts)]
+pub struct UpdateStablecoinOracle<'info> {
+    admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = protocol_config.bump,
+        has_one = admin @ AdminError::Unauthorized,
+    )]
+    protocol_config: Account<'info, ProtocolConfig>,
+    /// CHECK: Owner and layout are checked by the oracle backend in the new config
+    price_feed: UncheckedAccount<'info>,
+}
+
+impl<'info> UpdateStablecoinOracle<'info> {
+    // Rotate the price feed the interest rate controller reads the peg from
+    pub fn update_stablecoin_oracle(
+        &mut self,
+        stablecoin_price_feed: String,
+        stablecoin_oracle: OracleConfig,
+    ) -> Result<()> {
+        stablecoin_oracle.validate()?;
+
+        let stablecoin_price_feed = parse_feed_id(&stablecoin_price_feed)?;
+
+        // A feed that cannot be read would stop the interest rate controller
+        get_price(&self.price_feed, &stablecoin_price_feed, &stablecoin_oracle)?;
+
+        self.protocol_config.stablecoin_price_feed = stablecoin_price_feed;
+        self.protocol_config.stablecoin_oracle = stablecoin_oracle;
+
+        emit!(StablecoinOracleUpdated {
+            stablecoin_price_feed: self.protocol_config.stablecoin_price_feed,
+            stablecoin_oracle,
+        });
+
+        Ok(())
+    }
+}
@@ -3,73 +3,256 @@ pub mod instructions;
 pub use instructions::*;
 pub mod constants;
 pub mod errors;
+pub mod events;
+pub mod oracle;
+pub mod position_list;
+pub mod price_math;
 pub mod state;
+use state::{AuctionParams, LiquidationMode, OracleConfig, PauseFlags, SecondaryOracle};
 
 declare_id!("3xYBiBikqqFRLKJbctJ1ByaKr1cHGbBdhj9BSUTuTECa");
 
-#[program]
-pub mod cdp_stablecoin_protocol {
-    use super::*;
-
-    pub fn initialize_protocol_config(
-        ctx: Context<InitializeProtocolConfig>,
-        protocol_fee: u16,
-        redemption_fee: u16,
-        mint_fee: u16,
-        base_rate: u16,
-        sigma: u16,
-        stablecoin_price_feed: String,
-    ) -> Result<()> {
-        ctx.accounts.initialize_protocol_config(
-            protocol_fee,
-            redemption_fee,
-            mint_fee,
-            base_rate,
-            sigma,
-            stablecoin_price_feed,
-            &ctx.bumps,
-        )
-    }
+// Anchor 0.30 ignores #[cfg] on handlers, so handlers that only exist in some builds are
+// passed into the program module by the invocations below
+macro_rules! program_module {
+    ($($handler:item)*) => {
+        #[program]
+        pub mod cdp_stablecoin_protocol {
+            use super::*;
 
-    pub fn initialize_collateral_vault(
-        ctx: Context<InitializeCollateralVault>,
-        collateral_price_feed: String,
-    ) -> Result<()> {
-        ctx.accounts
-            .initialize_collateral_vault(collateral_price_feed, &ctx.bumps)
-    }
+            #[allow(clippy::too_many_arguments)]
+            pub fn initialize_protocol_config(
+                ctx: Context<InitializeProtocolConfig>,
+                protocol_fee: u16,
+                redemption_fee: u16,
+                mint_fee: u16,
+                base_rate: u16,
+                sigma: u16,
+                debt_ceiling: u64,
+                stablecoin_price_feed: String,
+                stablecoin_oracle: OracleConfig,
+            ) -> Result<()> {
+                ctx.accounts.initialize_protocol_config(
+                    protocol_fee,
+                    redemption_fee,
+                    mint_fee,
+                    base_rate,
+                    sigma,
+                    debt_ceiling,
+                    stablecoin_price_feed,
+                    stablecoin_oracle,
+                    &ctx.bumps,
+                )
+            }
 
-    pub fn open_position(
-        ctx: Context<OpenPosition>,
-        collateral_amount: u64,
-        debt_amount: u64,
-    ) -> Result<()> {
-        ctx.accounts.open_position(collateral_amount, debt_amount)
-    }
+            #[allow(clippy::too_many_arguments)]
+            pub fn initialize_collateral_vault(
+                ctx: Context<InitializeCollateralVault>,
+                collateral_price_feed: String,
+                collateral_oracle: OracleConfig,
+                secondary_oracle: Option<SecondaryOracle>,
+                max_ltv: u16,
+                liquidation_threshold: u16,
+                liquidation_penalty: u16,
+                close_factor: u16,
+                liquidator_reward: u16,
+                liquidation_mode: LiquidationMode,
+                auction_params: AuctionParams,
+                debt_ceiling: u64,
+                min_debt: u64,
+            ) -> Result<()> {
+                ctx.accounts.initialize_collateral_vault(
+                    collateral_price_feed,
+                    collateral_oracle,
+                    secondary_oracle,
+                    max_ltv,
+                    liquidation_threshold,
+                    liquidation_penalty,
+                    close_factor,
+                    liquidator_reward,
+                    liquidation_mode,
+                    auction_params,
+                    debt_ceiling,
+                    min_debt,
+                    &ctx.bumps,
+                )
+            }
 
-    pub fn close_position(ctx: Context<ClosePosition>) -> Result<()> {
-        ctx.accounts.close_position()
-    }
+            // instructions that change a position take the positions around its old and new
+            // place in the collateral's position list as remaining accounts
+            pub fn open_position<'info>(
+                ctx: Context<'_, '_, 'info, 'info, OpenPosition<'info>>,
+                collateral_amount: u64,
+                debt_amount: u64,
+            ) -> Result<()> {
+                ctx.accounts
+                    .open_position(collateral_amount, debt_amount, ctx.remaining_accounts)
+            }
 
-    pub fn update_interest_rate(ctx: Context<UpdateInterestRate>) -> Result<()> {
-        ctx.accounts.update_interest_rate()
-    }
+            pub fn close_position<'info>(
+                ctx: Context<'_, '_, 'info, 'info, ClosePosition<'info>>,
+            ) -> Result<()> {
+                ctx.accounts.close_position(ctx.remaining_accounts)
+            }
 
-    pub fn stake_stable_tokens(ctx: Context<Stake>, amount: u64) -> Result<()> {
-        ctx.accounts.init_stake_account(&ctx.bumps)?;
-        ctx.accounts.deposit_tokens(amount)
-    }
+            pub fn adjust_position<'info>(
+                ctx: Context<'_, '_, 'info, 'info, AdjustPosition<'info>>,
+                collateral_delta: i64,
+                debt_delta: i64,
+            ) -> Result<()> {
+                ctx.accounts
+                    .adjust_position(collateral_delta, debt_delta, ctx.remaining_accounts)
+            }
 
-    // claim stake rewards before unstaking
-    pub fn unstake_stable_tokens(ctx: Context<UnStake>) -> Result<()> {
-        ctx.accounts.withdraw_tokens(&ctx.bumps)
-    }
+            pub fn update_interest_rate(ctx: Context<UpdateInterestRate>) -> Result<()> {
+                ctx.accounts.update_interest_rate()
+            }
 
-    pub fn liquidate_position(ctx: Context<LiquidatePosition>) -> Result<()> {
-        ctx.accounts.liquidate_position()
-    }
+            pub fn stake_stable_tokens(ctx: Context<Stake>, amount: u64) -> Result<()> {
+                ctx.accounts.init_stake_account(&ctx.bumps)?;
+                ctx.accounts.deposit_tokens(amount)
+            }
+
+            // claim stake rewards before unstaking
+            pub fn unstake_stable_tokens(ctx: Context<UnStake>) -> Result<()> {
+                ctx.accounts.withdraw_tokens(&ctx.bumps)
+            }
+
+            pub fn liquidate_position<'info>(
+                ctx: Context<'_, '_, 'info, 'info, LiquidatePosition<'info>>,
+                debt_amount: u64,
+            ) -> Result<()> {
+                ctx.accounts
+                    .liquidate_position(debt_amount, ctx.remaining_accounts)
+            }
+
+            pub fn start_auction<'info>(
+                ctx: Context<'_, '_, 'info, 'info, StartAuction<'info>>,
+            ) -> Result<()> {
+                ctx.accounts
+                    .start_auction(&ctx.bumps, ctx.remaining_accounts)
+            }
+
+            pub fn bid_auction(ctx: Context<BidAuction>, collateral_amount: u64) -> Result<()> {
+                ctx.accounts.bid_auction(collateral_amount)
+            }
+
+            // restarts an auction that decayed to its floor without selling out
+            pub fn reset_auction(ctx: Context<ResetAuction>) -> Result<()> {
+                ctx.accounts.reset_auction()
+            }
 
-    pub fn claim_stake_reward(ctx: Context<ClaimStakeRewards>) -> Result<()> {
-        ctx.accounts.claim_stake_reward()
+            pub fn claim_stake_reward(ctx: Context<ClaimStakeRewards>) -> Result<()> {
+                ctx.accounts.claim_stake_reward()
+            }
+
+            pub fn collect_interest(ctx: Context<CollectInterest>) -> Result<()> {
+                ctx.accounts.collect_interest()
+            }
+
+            // the positions redeemed against are passed as remaining accounts, starting at the
+            // riskiest position of the collateral
+            pub fn redeem<'info>(
+                ctx: Context<'_, '_, 'info, 'info, Redeem<'info>>,
+                amount: u64,
+            ) -> Result<()> {
+                ctx.accounts.redeem(amount, ctx.remaining_accounts)
+            }
+
+            pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+                ctx.accounts.propose_admin(new_admin)
+            }
+
+            pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+                ctx.accounts.accept_admin()
+            }
+
+            pub fn update_protocol_config(
+                ctx: Context<UpdateProtocolConfig>,
+                protocol_fee: u16,
+                redemption_fee: u16,
+                mint_fee: u16,
+                base_rate: u16,
+                sigma: u16,
+                debt_ceiling: u64,
+            ) -> Result<()> {
+                ctx.accounts.update_protocol_config(
+                    protocol_fee,
+                    redemption_fee,
+                    mint_fee,
+                    base_rate,
+                    sigma,
+                    debt_ceiling,
+                )
+            }
+
+            pub fn set_collateral_debt_limits(
+                ctx: Context<SetCollateralDebtLimits>,
+                debt_ceiling: u64,
+                min_debt: u64,
+            ) -> Result<()> {
+                ctx.accounts
+                    .set_collateral_debt_limits(debt_ceiling, min_debt)
+            }
+
+            pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Pubkey) -> Result<()> {
+                ctx.accounts.set_guardian(guardian)
+            }
+
+            // the guardian can only pause, unpausing needs the admin
+            pub fn set_protocol_pause(ctx: Context<SetProtocolPause>, paused: PauseFlags) -> Result<()> {
+                ctx.accounts.set_protocol_pause(paused)
+            }
+
+            pub fn set_collateral_pause(
+                ctx: Context<SetCollateralPause>,
+                paused: PauseFlags,
+            ) -> Result<()> {
+                ctx.accounts.set_collateral_pause(paused)
+            }
+
+            pub fn update_collateral_oracle(
+                ctx: Context<UpdateCollateralOracle>,
+                collateral_price_feed: String,
+                collateral_oracle: OracleConfig,
+                secondary_oracle: Option<SecondaryOracle>,
+            ) -> Result<()> {
+                ctx.accounts.update_collateral_oracle(
+                    collateral_price_feed,
+                    collateral_oracle,
+                    secondary_oracle,
+                )
+            }
+
+            pub fn update_stablecoin_oracle(
+                ctx: Context<UpdateStablecoinOracle>,
+                stablecoin_price_feed: String,
+                stablecoin_oracle: OracleConfig,
+            ) -> Result<()> {
+                ctx.accounts
+                    .update_stablecoin_oracle(stablecoin_price_feed, stablecoin_oracle)
+            }
+
+            $($handler)*
+        }
+    };
+}
+
+// Mock prices are only settable in mock-oracle builds, and stay out of the production IDL
+#[cfg(feature = "mock-oracle")]
+program_module! {
+    pub fn set_mock_price(
+        ctx: Context<SetMockPrice>,
+        feed_id: [u8; 32],
+        price: i64,
+        ema_price: i64,
+        conf: u64,
+        exponent: i32,
+    ) -> Result<()> {
+        ctx.accounts
+            .set_mock_price(feed_id, price, ema_price, conf, exponent, &ctx.bumps)
     }
 }
+
+#[cfg(not(feature = "mock-oracle"))]
+program_module! {}
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+use super::{OraclePrice, PriceSource};
+use crate::{
+    errors::OracleError,
+    state::{MockPrice, PriceMode},
+};
+
+impl PriceSource for MockPrice {
+    fn read_price(&self, feed_id: &[u8; 32], mode: PriceMode) -> Result<OraclePrice> {
+        require!(self.feed_id == *feed_id, OracleError::InvalidPriceAccount);
+
+        Ok(OraclePrice {
+            price: self.price,
+            conf: self.conf,
+            ema_price: self.ema_price,
+            ema_conf: self.conf,
+            exponent: self.exponent,
+            publish_time: self.publish_time,
+            mode,
+        })
+    }
+}
@@ -0,0 +1,281 @@
+use anchor_lang::prelude::*;
+use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
+
+use crate::{
+    constants::BPS_SCALE,
+    errors::{ArithmeticError, OracleError},
+    state::{CollateralConfig, OracleConfig, OracleSource, PriceMode},
+};
+
+#[cfg(feature = "mock-oracle")]
+pub mod mock;
+pub mod pyth;
+pub mod switchboard;
+
+pub use switchboard::SwitchboardFeed;
+
+pub struct OraclePrice {
+    pub price: i64,
+    pub conf: u64,
+    pub ema_price: i64,
+    pub ema_conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub mode: PriceMode,
+}
+
+impl OraclePrice {
+    fn uses_spot(&self) -> bool {
+        self.mode != PriceMode::Ema
+    }
+
+    fn uses_ema(&self) -> bool {
+        self.mode != PriceMode::Spot
+    }
+
+    // Price for sizing seizures and auctions, where neither side needs protecting
+    pub fn reference_price(&self) -> i64 {
+        match self.mode {
+            PriceMode::Ema => self.ema_price,
+            _ => self.price,
+        }
+    }
+
+    // Conservative price when valuing collateral someone borrows against
+    pub fn lower_bound(&self) -> i64 {
+        let spot = self.price.saturating_sub(self.conf as i64);
+        let ema = self.ema_price.saturating_sub(self.ema_conf as i64);
+
+        let price = match self.mode {
+            PriceMode::Spot => spot,
+            PriceMode::Ema => ema,
+            PriceMode::Conservative => spot.min(ema),
+        };
+
+        price.max(0)
+    }
+
+    // Conservative price when deciding whether a position can be liquidated
+    pub fn upper_bound(&self) -> i64 {
+        let spot = self.price.saturating_add(self.conf as i64);
+        let ema = self.ema_price.saturating_add(self.ema_conf as i64);
+
+        match self.mode {
+            PriceMode::Spot => spot,
+            PriceMode::Ema => ema,
+            PriceMode::Conservative => spot.max(ema),
+        }
+    }
+
+    // Price nearest to target, so a short spike away from it is ignored in conservative mode
+    pub fn closest_to(&self, target: i64) -> i64 {
+        match self.mode {
+            PriceMode::Spot => self.price,
+            PriceMode::Ema => self.ema_price,
+            PriceMode::Conservative => {
+                if self.price.abs_diff(target) <= self.ema_price.abs_diff(target) {
+                    self.price
+                } else {
+                    self.ema_price
+                }
+            }
+        }
+    }
+
+    // Reject a price that is stale or whose confidence interval is too wide
+    pub fn validate(&self, oracle_config: &OracleConfig) -> Result<()> {
+        self.validate_at(oracle_config, Clock::get()?.unix_timestamp)
+    }
+
+    // Same checks against a given time, for off-chain callers without a clock
+    pub fn validate_at(&self, oracle_config: &OracleConfig, current_timestamp: i64) -> Result<()> {
+        if self.uses_spot() {
+            check_confidence(self.price, self.conf, oracle_config)?;
+        }
+        if self.uses_ema() {
+            check_confidence(self.ema_price, self.ema_conf, oracle_config)?;
+        }
+
+        require!(
+            self.publish_time
+                .saturating_add(oracle_config.max_price_age as i64)
+                >= current_timestamp,
+            OracleError::StalePrice
+        );
+
+        Ok(())
+    }
+
+    // Distance between two prices in bps of self, after bringing both to the same exponent
+    fn deviation_bps(&self, other: &OraclePrice) -> Result<u128> {
+        let exponent = self.exponent.min(other.exponent);
+
+        let scale = |price: &OraclePrice| -> Result<u128> {
+            Ok((price.reference_price().max(0) as u128)
+                .checked_mul(
+                    10_u128
+                        .checked_pow(price.exponent.abs_diff(exponent))
+                        .ok_or(ArithmeticError::ArithmeticOverflow)?,
+                )
+                .ok_or(ArithmeticError::ArithmeticOverflow)?)
+        };
+
+        let price = scale(self)?;
+        let other_price = scale(other)?;
+
+        Ok(price
+            .abs_diff(other_price)
+            .checked_mul(BPS_SCALE as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(price)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?)
+    }
+}
+
+// Implemented by every oracle backend, returns the raw prices of feed_id
+pub trait PriceSource {
+    fn read_price(&self, feed_id: &[u8; 32], mode: PriceMode) -> Result<OraclePrice>;
+}
+
+// Parse a hex feed id once when it is configured, so a typo fails the admin call
+// rather than every later price read
+pub fn parse_feed_id(price_feed: &str) -> Result<[u8; 32]> {
+    let feed_id = get_feed_id_from_hex(price_feed).map_err(|_| OracleError::InvalidFeedId)?;
+
+    require!(feed_id != [0; 32], OracleError::InvalidFeedId);
+
+    Ok(feed_id)
+}
+
+// Deserialize a price account after checking it belongs to the backend's program
+fn load_price_account<T: AccountDeserialize + Owner>(price_feed: &AccountInfo) -> Result<T> {
+    require_keys_eq!(
+        *price_feed.owner,
+        T::owner(),
+        OracleError::InvalidPriceAccount
+    );
+
+    let data = price_feed.try_borrow_data()?;
+    T::try_deserialize(&mut &data[..])
+}
+
+fn check_confidence(price: i64, conf: u64, oracle_config: &OracleConfig) -> Result<()> {
+    require!(price > 0, OracleError::InvalidPrice);
+
+    require!(
+        (conf as u128) * (BPS_SCALE as u128)
+            <= (price as u128) * (oracle_config.max_confidence as u128),
+        OracleError::PriceConfidenceTooWide
+    );
+
+    Ok(())
+}
+
+// Read the raw price of feed_id from the configured backend, also used off-chain where
+// there is no clock to validate against
+pub fn load_price(
+    price_feed: &AccountInfo,
+    feed_id: &[u8; 32],
+    oracle_config: &OracleConfig,
+) -> Result<OraclePrice> {
+    let mode = oracle_config.price_mode;
+
+    match oracle_config.source {
+        OracleSource::Pyth => {
+            load_price_account::<PriceUpdateV2>(price_feed)?.read_price(feed_id, mode)
+        }
+        OracleSource::Switchboard => SwitchboardFeed::load(price_feed)?.read_price(feed_id, mode),
+        #[cfg(feature = "mock-oracle")]
+        OracleSource::Mock => {
+            load_price_account::<crate::state::MockPrice>(price_feed)?.read_price(feed_id, mode)
+        }
+        #[cfg(not(feature = "mock-oracle"))]
+        OracleSource::Mock => err!(OracleError::MockOracleDisabled),
+    }
+}
+
+// Read a price from the configured backend and reject it if it is stale or its
+// confidence interval is too wide
+pub fn get_price(
+    price_feed: &AccountInfo,
+    feed_id: &[u8; 32],
+    oracle_config: &OracleConfig,
+) -> Result<OraclePrice> {
+    let price = load_price(price_feed, feed_id, oracle_config)?;
+
+    price.validate(oracle_config)?;
+
+    Ok(price)
+}
+
+// Price readable but failing validation counts as unavailable rather than an error
+fn get_usable_price(
+    price_feed: &AccountInfo,
+    feed_id: &[u8; 32],
+    oracle_config: &OracleConfig,
+) -> Result<Option<OraclePrice>> {
+    let price = load_price(price_feed, feed_id, oracle_config)?;
+
+    match price.validate(oracle_config) {
+        Ok(()) => Ok(Some(price)),
+        Err(error) => {
+            msg!("Rejected oracle price: {}", error);
+            Ok(None)
+        }
+    }
+}
+
+// Collateral price cross-checked against its secondary source when it has one. Falls
+// back to the secondary when the primary is unusable, and returns None when no price
+// can be trusted, which freezes opening debt, withdrawing and liquidating
+pub fn get_guarded_price(
+    price_feed: &AccountInfo,
+    secondary_price_feed: Option<&AccountInfo>,
+    collateral_config: &CollateralConfig,
+) -> Result<Option<OraclePrice>> {
+    let primary = get_usable_price(
+        price_feed,
+        &collateral_config.collateral_price_feed,
+        &collateral_config.collateral_oracle,
+    )?;
+
+    let Some(secondary_oracle) = collateral_config.secondary_oracle else {
+        return Ok(primary);
+    };
+
+    let secondary = get_usable_price(
+        secondary_price_feed.ok_or(OracleError::MissingSecondaryPriceFeed)?,
+        &secondary_oracle.price_feed,
+        &secondary_oracle.oracle,
+    )?;
+
+    let has_both = primary.is_some() && secondary.is_some();
+    let price = guard_prices(primary, secondary, secondary_oracle.max_deviation)?;
+
+    if has_both && price.is_none() {
+        msg!("Primary and secondary oracle prices deviate");
+    }
+
+    Ok(price)
+}
+
+// Pick the price to trust from the usable primary and secondary prices, None when
+// neither can be. Shared with off-chain clients that read the prices themselves.
+pub fn guard_prices(
+    primary: Option<OraclePrice>,
+    secondary: Option<OraclePrice>,
+    max_deviation: u16,
+) -> Result<Option<OraclePrice>> {
+    match (primary, secondary) {
+        (Some(primary), Some(secondary)) => {
+            if primary.deviation_bps(&secondary)? <= max_deviation as u128 {
+                Ok(Some(primary))
+            } else {
+                Ok(None)
+            }
+        }
+        (None, Some(secondary)) => Ok(Some(secondary)),
+        // Without a live secondary the primary cannot be cross-checked
+        _ => Ok(None),
+    }
+}
@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+use pyth_solana_receiver_sdk::price_update::{PriceUpdateV2, VerificationLevel};
+
+use super::{OraclePrice, PriceSource};
+use crate::{errors::OracleError, state::PriceMode};
+
+impl PriceSource for PriceUpdateV2 {
+    fn read_price(&self, feed_id: &[u8; 32], mode: PriceMode) -> Result<OraclePrice> {
+        // Only updates signed by a full Wormhole guardian quorum are trusted
+        require!(
+            self.verification_level.gte(VerificationLevel::Full),
+            OracleError::InsufficientVerification
+        );
+
+        // Checks the feed id, the EMA is read from the same message
+        let price = self.get_price_unchecked(feed_id)?;
+
+        Ok(OraclePrice {
+            price: price.price,
+            conf: price.conf,
+            ema_price: self.price_message.ema_price,
+            ema_conf: self.price_message.ema_conf,
+            exponent: price.exponent,
+            publish_time: price.publish_time,
+            mode,
+        })
+    }
+}
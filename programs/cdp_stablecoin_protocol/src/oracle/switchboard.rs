@@ -0,0 +1,86 @@
+use anchor_lang::{prelude::*, solana_program::hash::hash};
+
+use super::{OraclePrice, PriceSource};
+use crate::{errors::OracleError, state::PriceMode};
+
+// Switchboard on-demand program owning the pull feed accounts
+pub const SWITCHBOARD_ON_DEMAND_ID: Pubkey = pubkey!("SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv");
+
+// Offsets into the PullFeedAccountData layout: discriminator, 32 oracle submissions
+// of 64 bytes, then authority, queue, feed hash, init time, permissions, max variance,
+// min responses, name, padding and sample settings
+const LAST_UPDATE_TIMESTAMP_OFFSET: usize = 8 + 32 * 64 + 32 * 3 + 8 * 3 + 4 + 32 + 4;
+// followed by lut_slot and 32 reserved bytes, then the current result (value, std_dev, ...)
+const RESULT_VALUE_OFFSET: usize = LAST_UPDATE_TIMESTAMP_OFFSET + 8 + 8 + 32;
+const RESULT_STD_DEV_OFFSET: usize = RESULT_VALUE_OFFSET + 16;
+
+// Feed values carry 18 decimals, they are reduced to 8 to fit an i64 price
+const SWITCHBOARD_DECIMALS: i32 = 18;
+const SWITCHBOARD_EXPONENT: i32 = -8;
+
+// Median and spread of the latest oracle submissions of a pull feed
+pub struct SwitchboardFeed {
+    pub key: Pubkey,
+    pub value: i128,
+    pub std_dev: i128,
+    pub last_update_timestamp: i64,
+}
+
+impl SwitchboardFeed {
+    pub fn load(price_feed: &AccountInfo) -> Result<Self> {
+        require_keys_eq!(
+            *price_feed.owner,
+            SWITCHBOARD_ON_DEMAND_ID,
+            OracleError::InvalidPriceAccount
+        );
+
+        let data = price_feed.try_borrow_data()?;
+
+        require!(
+            data.len() >= RESULT_STD_DEV_OFFSET + 16
+                && data[..8] == hash(b"account:PullFeedAccountData").to_bytes()[..8],
+            OracleError::InvalidPriceAccount
+        );
+
+        let read_i128 =
+            |offset: usize| i128::from_le_bytes(data[offset..offset + 16].try_into().unwrap());
+
+        Ok(SwitchboardFeed {
+            key: price_feed.key(),
+            value: read_i128(RESULT_VALUE_OFFSET),
+            std_dev: read_i128(RESULT_STD_DEV_OFFSET),
+            last_update_timestamp: i64::from_le_bytes(
+                data[LAST_UPDATE_TIMESTAMP_OFFSET..LAST_UPDATE_TIMESTAMP_OFFSET + 8]
+                    .try_into()
+                    .unwrap(),
+            ),
+        })
+    }
+}
+
+impl PriceSource for SwitchboardFeed {
+    // A Switchboard feed is identified by its account address. It has no EMA, so the
+    // median stands in for it
+    fn read_price(&self, feed_id: &[u8; 32], mode: PriceMode) -> Result<OraclePrice> {
+        require!(
+            self.key.to_bytes() == *feed_id,
+            OracleError::InvalidPriceAccount
+        );
+
+        let scale = 10_i128.pow((SWITCHBOARD_DECIMALS + SWITCHBOARD_EXPONENT) as u32);
+
+        let price = i64::try_from(self.value / scale).map_err(|_| OracleError::InvalidPrice)?;
+        let conf = u64::try_from(self.std_dev.unsigned_abs() / scale as u128)
+            .map_err(|_| OracleError::InvalidPrice)?;
+
+        Ok(OraclePrice {
+            price,
+            conf,
+            ema_price: price,
+            ema_conf: conf,
+            exponent: SWITCHBOARD_EXPONENT,
+            publish_time: self.last_update_timestamp,
+            mode,
+        })
+    }
+}
@@ -0,0 +1,368 @@
+use std::ops::DerefMut;
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::{ArithmeticError, PositionError},
+    state::{CollateralConfig, Position},
+};
+
+// Positions of a collateral that carry debt form a doubly linked list, riskiest first,
+// starting at CollateralConfig::riskiest_position. Redemptions walk it from the head.
+//
+// Accounts cannot be looked up on chain, so an instruction that changes a position's
+// debt or collateral is passed the positions around it as remaining accounts: its current
+// neighbours, to unlink it, and the neighbours of the place its new risk falls into.
+pub struct PositionList<T> {
+    positions: Vec<T>,
+}
+
+impl<'info> PositionList<Account<'info, Position>> {
+    // Load the passed positions, rejecting duplicates, positions of other collaterals and
+    // the ones the instruction already holds in its accounts
+    pub fn load(
+        collateral_mint: &Pubkey,
+        held: &[Pubkey],
+        accounts: &'info [AccountInfo<'info>],
+    ) -> Result<Self> {
+        let mut positions: Vec<Account<'info, Position>> = Vec::with_capacity(accounts.len());
+
+        for account_info in accounts.iter() {
+            require!(
+                account_info.is_writable
+                    && !held.contains(account_info.key)
+                    && positions
+                        .iter()
+                        .all(|position| position.key() != account_info.key()),
+                PositionError::InvalidListPosition
+            );
+
+            let position: Account<'info, Position> = Account::try_from(account_info)?;
+
+            let (expected_position, _) = Pubkey::find_program_address(
+                &[
+                    b"position",
+                    position.user.as_ref(),
+                    collateral_mint.as_ref(),
+                ],
+                &crate::ID,
+            );
+            require_keys_eq!(
+                expected_position,
+                account_info.key(),
+                PositionError::InvalidListPosition
+            );
+
+            positions.push(position);
+        }
+
+        Ok(Self { positions })
+    }
+
+    pub fn exit(&self) -> Result<()> {
+        for position in self.positions.iter() {
+            position.exit(&crate::ID)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: Key + DerefMut<Target = Position>> PositionList<T> {
+    pub fn get(&self, key: Pubkey) -> Result<&Position> {
+        Ok(self
+            .positions
+            .iter()
+            .find(|position| position.key() == key)
+            .ok_or(PositionError::MissingListNeighbours)?)
+    }
+
+    pub fn get_mut(&mut self, key: Pubkey) -> Result<&mut Position> {
+        Ok(self
+            .positions
+            .iter_mut()
+            .find(|position| position.key() == key)
+            .ok_or(PositionError::MissingListNeighbours)?)
+    }
+
+    // Unlink a position, positions without debt are not in the list and are left alone
+    pub fn remove(
+        &mut self,
+        collateral_config: &mut CollateralConfig,
+        key: Pubkey,
+        position: &mut Position,
+    ) -> Result<()> {
+        if !is_listed(collateral_config, key, position) {
+            return Ok(());
+        }
+
+        match position.prev_position {
+            Some(prev) => self.get_mut(prev)?.next_position = position.next_position,
+            None => collateral_config.riskiest_position = position.next_position,
+        }
+
+        if let Some(next) = position.next_position {
+            self.get_mut(next)?.prev_position = position.prev_position;
+        }
+
+        position.prev_position = None;
+        position.next_position = None;
+
+        Ok(())
+    }
+
+    // Link an unlinked position between a passed position at least as risky and one at
+    // most as risky. Positions without debt stay out of the list.
+    pub fn insert(
+        &mut self,
+        collateral_config: &mut CollateralConfig,
+        key: Pubkey,
+        position: &mut Position,
+    ) -> Result<()> {
+        if position.debt_amount == 0 {
+            return Ok(());
+        }
+
+        let risk = collateral_config.position_risk(position)?;
+
+        // The position can go first, or after any listed position that was passed
+        let candidates: Vec<Option<Pubkey>> = std::iter::once(None)
+            .chain(
+                self.positions
+                    .iter()
+                    .filter(|neighbour| {
+                        neighbour.key() != key
+                            && is_listed(collateral_config, neighbour.key(), neighbour)
+                    })
+                    .map(|neighbour| Some(neighbour.key())),
+            )
+            .collect();
+
+        let mut place = None;
+
+        for prev in candidates {
+            let next = match prev {
+                Some(prev) => {
+                    let prev = self.get(prev)?;
+                    if !is_at_least_as_risky(collateral_config.position_risk(prev)?, risk)? {
+                        continue;
+                    }
+                    prev.next_position
+                }
+                None => collateral_config.riskiest_position,
+            };
+
+            if let Some(next) = next {
+                match self.get(next) {
+                    Ok(next) => {
+                        if !is_at_least_as_risky(risk, collateral_config.position_risk(next)?)? {
+                            continue;
+                        }
+                    }
+                    Err(_) => continue,
+                }
+            }
+
+            place = Some((prev, next));
+            break;
+        }
+
+        let (prev, next) = place.ok_or(PositionError::MissingListNeighbours)?;
+
+        match prev {
+            Some(prev) => self.get_mut(prev)?.next_position = Some(key),
+            None => collateral_config.riskiest_position = Some(key),
+        }
+
+        if let Some(next) = next {
+            self.get_mut(next)?.prev_position = Some(key);
+        }
+
+        position.prev_position = prev;
+        position.next_position = next;
+
+        Ok(())
+    }
+}
+
+fn is_listed(collateral_config: &CollateralConfig, key: Pubkey, position: &Position) -> bool {
+    position.prev_position.is_some() || collateral_config.riskiest_position == Some(key)
+}
+
+// Compares debt per unit of collateral by cross multiplying
+fn is_at_least_as_risky(risk: (u128, u128), other: (u128, u128)) -> Result<bool> {
+    Ok(risk
+        .0
+        .checked_mul(other.1)
+        .ok_or(ArithmeticError::ArithmeticOverflow)?
+        >= other
+            .0
+            .checked_mul(risk.1)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Deref;
+
+    use super::*;
+    use crate::constants::INTEREST_SCALE;
+
+    struct Entry(Pubkey, Position);
+
+    impl Key for Entry {
+        fn key(&self) -> Pubkey {
+            self.0
+        }
+    }
+
+    impl Deref for Entry {
+        type Target = Position;
+
+        fn deref(&self) -> &Position {
+            &self.1
+        }
+    }
+
+    impl DerefMut for Entry {
+        fn deref_mut(&mut self) -> &mut Position {
+            &mut self.1
+        }
+    }
+
+    fn zeroed<T: AnchorDeserialize + Space>() -> T {
+        T::deserialize(&mut &vec![0; T::INIT_SPACE][..]).unwrap()
+    }
+
+    fn position(collateral_amount: u64, debt_amount: u64) -> Position {
+        let mut position: Position = zeroed();
+        position.collateral_amount = collateral_amount;
+        position.debt_amount = debt_amount;
+        position.prev_cumulative_interest_rate = INTEREST_SCALE;
+        position
+    }
+
+    // Walk the list from its head, checking the back links on the way
+    fn walk(list: &PositionList<Entry>, collateral_config: &CollateralConfig) -> Vec<Pubkey> {
+        let mut keys = vec![];
+        let mut prev = None;
+        let mut cursor = collateral_config.riskiest_position;
+
+        while let Some(key) = cursor {
+            let position = list.get(key).unwrap();
+            assert_eq!(position.prev_position, prev);
+            keys.push(key);
+            prev = Some(key);
+            cursor = position.next_position;
+        }
+
+        keys
+    }
+
+    // Insert every position with the whole list passed
+    fn sorted(positions: Vec<Position>) -> (PositionList<Entry>, CollateralConfig, Vec<Pubkey>) {
+        let mut collateral_config: CollateralConfig = zeroed();
+        let keys: Vec<Pubkey> = positions.iter().map(|_| Pubkey::new_unique()).collect();
+        let mut list = PositionList {
+            positions: keys
+                .iter()
+                .zip(positions)
+                .map(|(key, position)| Entry(*key, position))
+                .collect(),
+        };
+
+        for key in keys.iter() {
+            let mut position = list.get(*key).unwrap().clone();
+            list.insert(&mut collateral_config, *key, &mut position)
+                .unwrap();
+            *list.get_mut(*key).unwrap() = position;
+        }
+
+        (list, collateral_config, keys)
+    }
+
+    #[test]
+    fn positions_are_sorted_riskiest_first() {
+        let (list, collateral_config, keys) = sorted(vec![
+            position(1_000_000, 500_000),
+            position(1_000_000, 800_000),
+            position(2_000_000, 0),
+            position(2_000_000, 600_000),
+        ]);
+
+        assert_eq!(
+            walk(&list, &collateral_config),
+            vec![keys[1], keys[0], keys[3]]
+        );
+    }
+
+    #[test]
+    fn moving_a_position_needs_its_neighbours() {
+        let (mut list, mut collateral_config, keys) = sorted(vec![
+            position(1_000_000, 500_000),
+            position(1_000_000, 800_000),
+            position(1_000_000, 600_000),
+        ]);
+
+        // Unlinking the middle position needs both of its neighbours
+        let mut middle = list.get(keys[2]).unwrap().clone();
+        let mut partial = PositionList {
+            positions: vec![Entry(keys[1], list.get(keys[1]).unwrap().clone())],
+        };
+        assert!(partial
+            .remove(&mut collateral_config.clone(), keys[2], &mut middle)
+            .is_err());
+
+        // Repaying makes it the safest, it has to be placed after the previous tail
+        list.remove(&mut collateral_config, keys[2], &mut middle)
+            .unwrap();
+        middle.debt_amount = 100_000;
+        list.insert(&mut collateral_config, keys[2], &mut middle)
+            .unwrap();
+        *list.get_mut(keys[2]).unwrap() = middle;
+
+        assert_eq!(
+            walk(&list, &collateral_config),
+            vec![keys[1], keys[0], keys[2]]
+        );
+
+        // Without the positions it falls between there is nowhere to insert it
+        let mut riskiest = list.get(keys[1]).unwrap().clone();
+        list.remove(&mut collateral_config, keys[1], &mut riskiest)
+            .unwrap();
+        *list.get_mut(keys[1]).unwrap() = riskiest.clone();
+        riskiest.debt_amount = 300_000;
+
+        let mut partial = PositionList {
+            positions: vec![Entry(keys[0], list.get(keys[0]).unwrap().clone())],
+        };
+        assert!(partial
+            .insert(&mut collateral_config, keys[1], &mut riskiest)
+            .is_err());
+    }
+
+    #[test]
+    fn redistribution_keeps_the_order() {
+        let (list, mut collateral_config, keys) = sorted(vec![
+            position(1_000_000, 500_000),
+            position(3_000_000, 2_000_000),
+        ]);
+
+        // Debt spread pro rata to collateral raises every ratio without reordering them
+        collateral_config.collateral_amount = 5_000_000;
+        collateral_config
+            .redistribute(1_000_000, 1_000_000, 1_000_000, INTEREST_SCALE)
+            .unwrap();
+
+        let first = collateral_config
+            .position_risk(list.get(keys[1]).unwrap())
+            .unwrap();
+        let second = collateral_config
+            .position_risk(list.get(keys[0]).unwrap())
+            .unwrap();
+
+        assert_eq!(first, (2_750_000, 3_750_000));
+        assert!(is_at_least_as_risky(first, second).unwrap());
+        assert_eq!(walk(&list, &collateral_config), vec![keys[1], keys[0]]);
+    }
+}
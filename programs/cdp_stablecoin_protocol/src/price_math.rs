@@ -0,0 +1,189 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::{BPS_SCALE, STABLE_DECIMALS},
+    errors::ArithmeticError,
+};
+
+// Power of ten taking collateral base units times an oracle price (price * 10^exponent
+// dollars per whole token) to stablecoin base units
+fn value_exponent(exponent: i32, collateral_decimals: u8) -> i32 {
+    STABLE_DECIMALS as i32 + exponent - collateral_decimals as i32
+}
+
+fn pow10(power: u32) -> Result<u128> {
+    Ok(10_u128
+        .checked_pow(power)
+        .ok_or(ArithmeticError::ArithmeticOverflow)?)
+}
+
+// Value of collateral_amount in stablecoin base units, rounded down
+pub fn collateral_value(
+    collateral_amount: u64,
+    price: i64,
+    exponent: i32,
+    collateral_decimals: u8,
+) -> Result<u128> {
+    let value = (collateral_amount as u128)
+        .checked_mul(price.max(0) as u128)
+        .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+    let power = value_exponent(exponent, collateral_decimals);
+
+    let value = if power >= 0 {
+        value.checked_mul(pow10(power.unsigned_abs())?)
+    } else {
+        value.checked_div(pow10(power.unsigned_abs())?)
+    };
+
+    Ok(value.ok_or(ArithmeticError::ArithmeticOverflow)?)
+}
+
+// Collateral base units worth value stablecoin base units, rounded down
+pub fn collateral_amount(
+    value: u64,
+    price: i64,
+    exponent: i32,
+    collateral_decimals: u8,
+) -> Result<u128> {
+    let power = value_exponent(exponent, collateral_decimals);
+
+    let amount = if power >= 0 {
+        (value as u128).checked_div(
+            (price.max(0) as u128)
+                .checked_mul(pow10(power.unsigned_abs())?)
+                .ok_or(ArithmeticError::ArithmeticOverflow)?,
+        )
+    } else {
+        (value as u128)
+            .checked_mul(pow10(power.unsigned_abs())?)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(price.max(0) as u128)
+    };
+
+    Ok(amount.ok_or(ArithmeticError::ArithmeticOverflow)?)
+}
+
+// Collateral paid out for redeemed_debt, worth it at price less the redemption fee in bps
+pub fn redeemed_collateral(
+    redeemed_debt: u64,
+    price: i64,
+    exponent: i32,
+    collateral_decimals: u8,
+    redemption_fee: u16,
+) -> Result<u64> {
+    Ok(
+        collateral_amount(redeemed_debt, price, exponent, collateral_decimals)?
+            .checked_mul(
+                BPS_SCALE
+                    .checked_sub(redemption_fee)
+                    .ok_or(ArithmeticError::ArithmeticOverflow)? as u128,
+            )
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(BPS_SCALE as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)? as u64,
+    )
+}
+
+// Debt over collateral value in bps, worthless collateral counts as infinitely risky
+// unless there is no debt, like an emptied position that is about to be closed
+pub fn ltv(debt: u64, collateral_value: u128) -> Result<u128> {
+    match collateral_value {
+        0 if debt == 0 => Ok(0),
+        0 => Ok(u128::MAX),
+        _ => Ok((debt as u128)
+            .checked_mul(BPS_SCALE as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(collateral_value)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collateral_value_scales_by_decimals() {
+        // 1 token at $200 with 6 decimals
+        assert_eq!(
+            collateral_value(1_000_000, 200_00000000, -8, 6).unwrap(),
+            200_000_000
+        );
+        // 1 token at $60,000 with 8 decimals
+        assert_eq!(
+            collateral_value(100_000_000, 6_000_000_000_000, -8, 8).unwrap(),
+            60_000_000_000
+        );
+        // 1 token at $150 with 9 decimals
+        assert_eq!(
+            collateral_value(1_000_000_000, 150_00000000, -8, 9).unwrap(),
+            150_000_000
+        );
+    }
+
+    #[test]
+    fn collateral_value_with_positive_exponent() {
+        // 1 token at 3 * 10^2 dollars
+        assert_eq!(collateral_value(1_000_000, 3, 2, 6).unwrap(), 300_000_000);
+        // 5 whole tokens without decimals at $12.34
+        assert_eq!(collateral_value(5, 1234, -2, 0).unwrap(), 61_700_000);
+    }
+
+    #[test]
+    fn collateral_amount_scales_by_decimals() {
+        assert_eq!(
+            collateral_amount(200_000_000, 200_00000000, -8, 6).unwrap(),
+            1_000_000
+        );
+        assert_eq!(
+            collateral_amount(60_000_000_000, 6_000_000_000_000, -8, 8).unwrap(),
+            100_000_000
+        );
+        assert_eq!(
+            collateral_amount(150_000_000, 150_00000000, -8, 9).unwrap(),
+            1_000_000_000
+        );
+        assert_eq!(collateral_amount(300_000_000, 3, 2, 6).unwrap(), 1_000_000);
+    }
+
+    #[test]
+    fn round_trip_rounds_down() {
+        let amount = 1_234_567_890;
+        let value = collateral_value(amount, 150_00000000, -8, 9).unwrap();
+        let back = collateral_amount(value as u64, 150_00000000, -8, 9).unwrap();
+
+        // Both directions round down, losing less than one stablecoin unit of collateral
+        assert!(back <= amount as u128);
+        assert!(amount as u128 - back < 7);
+    }
+
+    #[test]
+    fn zero_and_negative_prices() {
+        assert_eq!(collateral_value(1_000_000, 0, -8, 6).unwrap(), 0);
+        assert_eq!(collateral_value(1_000_000, -1, -8, 6).unwrap(), 0);
+        assert!(collateral_amount(1_000_000, 0, -8, 6).is_err());
+        assert_eq!(ltv(1_000_000, 0).unwrap(), u128::MAX);
+        assert_eq!(ltv(0, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn ltv_in_bps() {
+        assert_eq!(ltv(50_000_000, 100_000_000).unwrap(), 5_000);
+        assert_eq!(ltv(0, 100_000_000).unwrap(), 0);
+        assert_eq!(ltv(150_000_000, 100_000_000).unwrap(), 15_000);
+    }
+
+    #[test]
+    fn redeemed_collateral_is_net_of_the_fee() {
+        // $100 of debt at $200 is half a token, less 5%
+        assert_eq!(
+            redeemed_collateral(100_000_000, 200_00000000, -8, 6, 500).unwrap(),
+            475_000
+        );
+        assert_eq!(
+            redeemed_collateral(100_000_000, 200_00000000, -8, 6, 0).unwrap(),
+            500_000
+        );
+    }
+}
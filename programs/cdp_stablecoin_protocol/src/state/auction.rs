@@ -0,0 +1,146 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::BPS_SCALE,
+    errors::{ArithmeticError, CollateralError},
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq)]
+pub enum LiquidationMode {
+    StabilityPool,
+    Auction,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy)]
+pub struct AuctionParams {
+    // Premium over the oracle price the auction starts at
+    pub start_premium: u16,
+    // Fraction of the oracle price the auction decays to
+    pub floor: u16,
+    pub duration: i64,
+}
+
+impl AuctionParams {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.floor > 0 && (self.floor as u32) < BPS_SCALE as u32 + self.start_premium as u32,
+            CollateralError::InvalidAuctionParameters
+        );
+        require!(self.duration > 0, CollateralError::InvalidAuctionParameters);
+
+        Ok(())
+    }
+
+    // Start and floor prices of an auction around the oracle reference price
+    pub fn price_range(&self, reference_price: i64) -> Result<(u64, u64)> {
+        let start_price = (reference_price as u128)
+            .checked_mul(BPS_SCALE as u128 + self.start_premium as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(BPS_SCALE as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)? as u64;
+
+        let floor_price = (reference_price as u128)
+            .checked_mul(self.floor as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(BPS_SCALE as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)? as u64;
+
+        Ok((start_price, floor_price))
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Auction {
+    pub id: u64,
+    pub owner: Pubkey,
+    pub keeper: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub collateral_amount: u64,
+    pub debt_amount: u64,
+    pub prev_cumulative_interest_rate: u128,
+    pub start_price: u64,
+    pub floor_price: u64,
+    pub price_exponent: i32,
+    pub start_time: i64,
+    pub duration: i64,
+    pub bump: u8,
+}
+
+impl Auction {
+    // Linear decay from the start price to the floor, flat at the floor afterwards
+    pub fn current_price(&self, now: i64) -> Result<u64> {
+        let elapsed = now.saturating_sub(self.start_time).clamp(0, self.duration);
+
+        let decay = ((self.start_price - self.floor_price) as u128)
+            .checked_mul(elapsed as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(self.duration as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)? as u64;
+
+        Ok(self.start_price - decay)
+    }
+
+    pub fn calculate_current_debt(&self, cumulative_interest_rate: u128) -> Result<u64> {
+        let current_debt = (self.debt_amount as u128)
+            .checked_mul(cumulative_interest_rate)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(self.prev_cumulative_interest_rate)
+            .ok_or(ArithmeticError::ArithmeticOverflow)? as u64;
+
+        Ok(current_debt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auction(start_price: u64, floor_price: u64) -> Auction {
+        Auction {
+            id: 0,
+            owner: Pubkey::default(),
+            keeper: Pubkey::default(),
+            collateral_mint: Pubkey::default(),
+            collateral_amount: 1_000_000,
+            debt_amount: 1_000_000,
+            prev_cumulative_interest_rate: 1,
+            start_price,
+            floor_price,
+            price_exponent: -8,
+            start_time: 1_000,
+            duration: 100,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn current_price_decays_linearly_to_the_floor() {
+        let auction = auction(11_000, 8_000);
+
+        assert_eq!(auction.current_price(1_000).unwrap(), 11_000);
+        assert_eq!(auction.current_price(1_050).unwrap(), 9_500);
+        assert_eq!(auction.current_price(1_099).unwrap(), 8_030);
+        assert_eq!(auction.current_price(1_100).unwrap(), 8_000);
+    }
+
+    #[test]
+    fn current_price_is_clamped_outside_the_auction() {
+        let auction = auction(11_000, 8_000);
+
+        assert_eq!(auction.current_price(0).unwrap(), 11_000);
+        assert_eq!(auction.current_price(1_000_000).unwrap(), 8_000);
+        assert_eq!(auction.current_price(i64::MAX).unwrap(), 8_000);
+    }
+
+    #[test]
+    fn price_range_applies_premium_and_floor() {
+        let params = AuctionParams {
+            start_premium: 1_000,
+            floor: 8_000,
+            duration: 100,
+        };
+
+        assert_eq!(params.price_range(10_000).unwrap(), (11_000, 8_000));
+    }
+}
@@ -1,15 +1,450 @@
 use anchor_lang::prelude::*;
 
+use crate::{
+    constants::{BPS_SCALE, GAIN_SCALE, INTEREST_SCALE, REDISTRIBUTION_SCALE},
+    errors::{ArithmeticError, CollateralError, LiquidationError, PositionError},
+    state::{
+        AuctionParams, LiquidationMode, OracleConfig, PauseFlags, Position, ProtocolConfig,
+        SecondaryOracle,
+    },
+};
+
 #[account]
 #[derive(InitSpace)]
 pub struct CollateralConfig {
     pub mint: Pubkey,
-    #[max_len(100)]
-    pub collateral_price_feed: String,
+    pub collateral_price_feed: [u8; 32],
+    pub collateral_oracle: OracleConfig,
+    pub secondary_oracle: Option<SecondaryOracle>,
     pub vault: Pubkey,
     pub collateral_amount: u64,
     pub stability_pool_rewards_amount: u64,
     pub gain_summation: u128,
+    pub max_ltv: u16,
+    pub liquidation_threshold: u16,
+    pub liquidation_penalty: u16,
+    pub close_factor: u16,
+    pub liquidator_reward: u16,
+    pub redistributed_collateral_per_unit: u128,
+    pub redistributed_debt_per_unit: u128,
+    pub liquidation_mode: LiquidationMode,
+    pub auction_params: AuctionParams,
+    pub auction_count: u64,
+    pub paused: PauseFlags,
+    // Most debt that can be minted against this collateral, with accrued interest
+    pub debt_ceiling: u64,
+    // Outstanding debt of positions and auctions at the interest index of 1
+    pub normalized_debt: u128,
+    // Smallest debt a position may carry, other than none at all
+    pub min_debt: u64,
+    // Stability pool epoch gain_summation last moved in, and the epoch and value it had
+    // reached before that, which deposits of older epochs are settled against
+    pub stake_epoch: u64,
+    pub prev_stake_epoch: u64,
+    pub prev_epoch_gain_summation: u128,
+    // Auction shortfalls written off because no position was left to take them over,
+    // stablecoins in circulation that no debt backs
+    pub bad_debt: u64,
+    // Riskiest position carrying debt, the head of the list redemptions walk
+    pub riskiest_position: Option<Pubkey>,
     pub bump: u8,
     pub vault_bump: u8,
 }
+
+impl CollateralConfig {
+    // Borrow limit must sit below the liquidation threshold, and a position at the
+    // threshold must still hold enough collateral to cover its debt plus the penalty
+    pub fn validate_risk_parameters(
+        max_ltv: u16,
+        liquidation_threshold: u16,
+        liquidation_penalty: u16,
+        close_factor: u16,
+        liquidator_reward: u16,
+    ) -> Result<()> {
+        require!(
+            max_ltv > 0 && max_ltv < liquidation_threshold && liquidation_threshold < BPS_SCALE,
+            CollateralError::InvalidRiskParameters
+        );
+
+        require!(
+            (liquidation_threshold as u32) * (BPS_SCALE as u32 + liquidation_penalty as u32)
+                <= (BPS_SCALE as u32) * (BPS_SCALE as u32),
+            CollateralError::InvalidRiskParameters
+        );
+
+        require!(
+            close_factor > 0 && close_factor <= BPS_SCALE,
+            CollateralError::InvalidRiskParameters
+        );
+
+        // After paying the liquidator the pool must still receive at least the debt it burns
+        require!(
+            (BPS_SCALE as u32 + liquidation_penalty as u32)
+                * (BPS_SCALE as u32 - liquidator_reward.min(BPS_SCALE) as u32)
+                >= (BPS_SCALE as u32) * (BPS_SCALE as u32),
+            CollateralError::InvalidRiskParameters
+        );
+
+        Ok(())
+    }
+
+    // Credit a position with its share of collateral and debt redistributed since its last
+    // snapshot. Accrued interest is settled on the way so the position is fully re-based.
+    pub fn apply_pending_redistribution(
+        &mut self,
+        position: &mut Position,
+        protocol_config: &ProtocolConfig,
+    ) -> Result<()> {
+        let current_debt = protocol_config.calculate_current_debt(position)?;
+
+        let pending_collateral = (position.collateral_amount as u128)
+            .checked_mul(
+                self.redistributed_collateral_per_unit
+                    .checked_sub(position.redistributed_collateral_snapshot)
+                    .ok_or(ArithmeticError::ArithmeticOverflow)?,
+            )
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(REDISTRIBUTION_SCALE)
+            .ok_or(ArithmeticError::ArithmeticOverflow)? as u64;
+
+        // Redistributed debt is tracked at the interest index of 1, scale it to today
+        let pending_debt = (position.collateral_amount as u128)
+            .checked_mul(
+                self.redistributed_debt_per_unit
+                    .checked_sub(position.redistributed_debt_snapshot)
+                    .ok_or(ArithmeticError::ArithmeticOverflow)?,
+            )
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(REDISTRIBUTION_SCALE)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_mul(protocol_config.cumulative_interest_rate)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(INTEREST_SCALE)
+            .ok_or(ArithmeticError::ArithmeticOverflow)? as u64;
+
+        position.debt_amount = current_debt
+            .checked_add(pending_debt)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+        position.collateral_amount = position
+            .collateral_amount
+            .checked_add(pending_collateral)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+        position.prev_cumulative_interest_rate = protocol_config.cumulative_interest_rate;
+        position.redistributed_collateral_snapshot = self.redistributed_collateral_per_unit;
+        position.redistributed_debt_snapshot = self.redistributed_debt_per_unit;
+
+        // Redistributed collateral never left the vault, it only becomes a position's again
+        self.collateral_amount = self
+            .collateral_amount
+            .checked_add(pending_collateral)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+        Ok(())
+    }
+
+    // Debt at the interest index of 1 and collateral of a position, counting what is still
+    // pending from redistributions. Their ratio ranks positions by risk, and interest and
+    // redistribution change it alike for every position, so the ranking only moves when
+    // a position itself is changed.
+    pub fn position_risk(&self, position: &Position) -> Result<(u128, u128)> {
+        let normalized_debt = (position.debt_amount as u128)
+            .checked_mul(INTEREST_SCALE)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(position.prev_cumulative_interest_rate)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_add(
+                (position.collateral_amount as u128)
+                    .checked_mul(
+                        self.redistributed_debt_per_unit
+                            .checked_sub(position.redistributed_debt_snapshot)
+                            .ok_or(ArithmeticError::ArithmeticOverflow)?,
+                    )
+                    .ok_or(ArithmeticError::ArithmeticOverflow)?
+                    .checked_div(REDISTRIBUTION_SCALE)
+                    .ok_or(ArithmeticError::ArithmeticOverflow)?,
+            )
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+        let collateral = (position.collateral_amount as u128)
+            .checked_add(
+                (position.collateral_amount as u128)
+                    .checked_mul(
+                        self.redistributed_collateral_per_unit
+                            .checked_sub(position.redistributed_collateral_snapshot)
+                            .ok_or(ArithmeticError::ArithmeticOverflow)?,
+                    )
+                    .ok_or(ArithmeticError::ArithmeticOverflow)?
+                    .checked_div(REDISTRIBUTION_SCALE)
+                    .ok_or(ArithmeticError::ArithmeticOverflow)?,
+            )
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+        Ok((normalized_debt, collateral))
+    }
+
+    // Spread collateral and debt the stability pool could not absorb over every other
+    // position of this collateral, pro rata to their recorded collateral
+    pub fn redistribute(
+        &mut self,
+        collateral: u64,
+        debt: u64,
+        excluded_collateral: u64,
+        cumulative_interest_rate: u128,
+    ) -> Result<()> {
+        let total_collateral = self
+            .collateral_amount
+            .checked_sub(excluded_collateral)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+        require!(
+            total_collateral > 0,
+            LiquidationError::NoPositionsToRedistribute
+        );
+
+        self.redistributed_collateral_per_unit = self
+            .redistributed_collateral_per_unit
+            .checked_add(
+                (collateral as u128)
+                    .checked_mul(REDISTRIBUTION_SCALE)
+                    .ok_or(ArithmeticError::ArithmeticOverflow)?
+                    .checked_div(total_collateral as u128)
+                    .ok_or(ArithmeticError::ArithmeticOverflow)?,
+            )
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+        self.redistributed_debt_per_unit = self
+            .redistributed_debt_per_unit
+            .checked_add(
+                (debt as u128)
+                    .checked_mul(INTEREST_SCALE)
+                    .ok_or(ArithmeticError::ArithmeticOverflow)?
+                    .checked_div(cumulative_interest_rate)
+                    .ok_or(ArithmeticError::ArithmeticOverflow)?
+                    .checked_mul(REDISTRIBUTION_SCALE)
+                    .ok_or(ArithmeticError::ArithmeticOverflow)?
+                    .checked_div(total_collateral as u128)
+                    .ok_or(ArithmeticError::ArithmeticOverflow)?,
+            )
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+        Ok(())
+    }
+
+    // Credit collateral moved to the liquidation rewards vault to the current stability
+    // pool deposits, keeping the value gain_summation reached in earlier epochs
+    pub fn reward_stability_pool(
+        &mut self,
+        collateral: u64,
+        protocol_config: &ProtocolConfig,
+    ) -> Result<()> {
+        if self.stake_epoch < protocol_config.stake_epoch {
+            self.prev_stake_epoch = self.stake_epoch;
+            self.prev_epoch_gain_summation = self.gain_summation;
+            self.stake_epoch = protocol_config.stake_epoch;
+        }
+
+        self.stability_pool_rewards_amount = self
+            .stability_pool_rewards_amount
+            .checked_add(collateral)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+        self.gain_summation = self
+            .gain_summation
+            .checked_add(
+                (collateral as u128)
+                    .checked_mul(GAIN_SCALE)
+                    .ok_or(ArithmeticError::ArithmeticOverflow)?
+                    .checked_mul(protocol_config.deposit_depletion_factor as u128)
+                    .ok_or(ArithmeticError::ArithmeticOverflow)?
+                    .checked_div(BPS_SCALE as u128)
+                    .ok_or(ArithmeticError::ArithmeticOverflow)?
+                    .checked_div(protocol_config.total_stake_amount)
+                    .ok_or(ArithmeticError::ArithmeticOverflow)?,
+            )
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+        Ok(())
+    }
+
+    // Outstanding debt of this collateral at today's interest index
+    pub fn current_debt(&self, cumulative_interest_rate: u128) -> Result<u128> {
+        Ok(self
+            .normalized_debt
+            .checked_mul(cumulative_interest_rate)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(INTEREST_SCALE)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?)
+    }
+
+    // Mirror of ProtocolConfig::update_totals, repayments saturate at zero since
+    // normalising each change rounds
+    pub fn update_debt(&mut self, debt_change: i64, cumulative_interest_rate: u128) -> Result<()> {
+        let normalized_change = (debt_change.unsigned_abs() as u128)
+            .checked_mul(INTEREST_SCALE)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(cumulative_interest_rate)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+        if debt_change > 0 {
+            self.normalized_debt = self
+                .normalized_debt
+                .checked_add(normalized_change)
+                .ok_or(ArithmeticError::ArithmeticOverflow)?;
+        } else {
+            self.normalized_debt = self.normalized_debt.saturating_sub(normalized_change);
+        }
+
+        Ok(())
+    }
+
+    // Dust positions cost more to liquidate than they are worth, so debt is either
+    // repaid fully or kept above the minimum
+    pub fn check_min_debt(&self, debt_amount: u64) -> Result<()> {
+        require!(
+            debt_amount == 0 || debt_amount >= self.min_debt,
+            PositionError::DebtBelowMinimum
+        );
+
+        Ok(())
+    }
+
+    // Largest part of `reduction` that can be taken off `debt_amount` without leaving
+    // dust behind, for repayments that are not chosen by the position owner
+    pub fn cap_debt_reduction(&self, debt_amount: u64, reduction: u64) -> u64 {
+        if reduction >= debt_amount {
+            return debt_amount;
+        }
+
+        reduction.min(debt_amount.saturating_sub(self.min_debt))
+    }
+
+    // Debt nobody can be charged for leaves the debt totals, so no interest accrues on it
+    pub fn write_off_debt(
+        &mut self,
+        debt: u64,
+        protocol_config: &mut ProtocolConfig,
+    ) -> Result<()> {
+        protocol_config.update_totals(-(debt as i64))?;
+        self.update_debt(-(debt as i64), protocol_config.cumulative_interest_rate)?;
+
+        self.bad_debt = self
+            .bad_debt
+            .checked_add(debt)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+        Ok(())
+    }
+
+    pub fn check_debt_ceiling(&self, cumulative_interest_rate: u128) -> Result<()> {
+        require!(
+            self.current_debt(cumulative_interest_rate)? <= self.debt_ceiling as u128,
+            PositionError::CollateralDebtCeilingExceeded
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zeroed<T: AnchorDeserialize + Space>() -> T {
+        T::deserialize(&mut &vec![0; T::INIT_SPACE][..]).unwrap()
+    }
+
+    fn position(collateral_amount: u64, debt_amount: u64) -> Position {
+        Position {
+            user: Pubkey::default(),
+            collateral_amount,
+            debt_amount,
+            prev_cumulative_interest_rate: INTEREST_SCALE,
+            redistributed_collateral_snapshot: 0,
+            redistributed_debt_snapshot: 0,
+            prev_position: None,
+            next_position: None,
+        }
+    }
+
+    #[test]
+    fn redistribution_is_settled_pro_rata() {
+        let mut protocol_config: ProtocolConfig = zeroed();
+        protocol_config.cumulative_interest_rate = INTEREST_SCALE;
+        let mut collateral_config: CollateralConfig = zeroed();
+
+        // The liquidated position holds 1_000_000 of the 4_000_000 collateral
+        collateral_config.collateral_amount = 4_000_000;
+        let mut small = position(1_000_000, 1_000_000);
+        let mut large = position(2_000_000, 1_000_000);
+
+        collateral_config
+            .redistribute(600_000, 1_500_000, 1_000_000, INTEREST_SCALE)
+            .unwrap();
+
+        collateral_config
+            .apply_pending_redistribution(&mut small, &protocol_config)
+            .unwrap();
+        collateral_config
+            .apply_pending_redistribution(&mut large, &protocol_config)
+            .unwrap();
+
+        assert_eq!(small.collateral_amount, 1_200_000);
+        assert_eq!(small.debt_amount, 1_500_000);
+        assert_eq!(large.collateral_amount, 2_400_000);
+        assert_eq!(large.debt_amount, 2_000_000);
+
+        // Settling again changes nothing
+        collateral_config
+            .apply_pending_redistribution(&mut large, &protocol_config)
+            .unwrap();
+        assert_eq!(large.debt_amount, 2_000_000);
+    }
+
+    #[test]
+    fn redistributed_debt_accrues_interest() {
+        let mut protocol_config: ProtocolConfig = zeroed();
+        protocol_config.cumulative_interest_rate = INTEREST_SCALE;
+        let mut collateral_config: CollateralConfig = zeroed();
+        collateral_config.collateral_amount = 2_000_000;
+        let mut position = position(1_000_000, 1_000_000);
+
+        collateral_config
+            .redistribute(0, 1_000_000, 1_000_000, INTEREST_SCALE)
+            .unwrap();
+
+        // 10% of interest later both the position's own and its redistributed debt grew
+        protocol_config.cumulative_interest_rate = INTEREST_SCALE * 11 / 10;
+        collateral_config
+            .apply_pending_redistribution(&mut position, &protocol_config)
+            .unwrap();
+
+        assert_eq!(position.debt_amount, 2_200_000);
+    }
+
+    #[test]
+    fn written_off_debt_leaves_the_totals() {
+        let mut protocol_config: ProtocolConfig = zeroed();
+        protocol_config.cumulative_interest_rate = INTEREST_SCALE * 2;
+        protocol_config.total_debt = 3_000_000;
+        let mut collateral_config: CollateralConfig = zeroed();
+        collateral_config.normalized_debt = 1_500_000;
+
+        collateral_config
+            .write_off_debt(1_000_000, &mut protocol_config)
+            .unwrap();
+
+        assert_eq!(protocol_config.total_debt, 2_000_000);
+        assert_eq!(collateral_config.normalized_debt, 1_000_000);
+        assert_eq!(collateral_config.bad_debt, 1_000_000);
+    }
+
+    #[test]
+    fn redistribution_needs_other_positions() {
+        let mut collateral_config: CollateralConfig = zeroed();
+        collateral_config.collateral_amount = 1_000_000;
+
+        assert!(collateral_config
+            .redistribute(0, 1_000_000, 1_000_000, INTEREST_SCALE)
+            .is_err());
+    }
+}
@@ -0,0 +1,15 @@
+use anchor_lang::prelude::*;
+
+// Price written by the admin for localnet tests, read through OracleSource::Mock. Only
+// compiled into mock-oracle builds
+#[account]
+#[derive(InitSpace)]
+pub struct MockPrice {
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub ema_price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub bump: u8,
+}
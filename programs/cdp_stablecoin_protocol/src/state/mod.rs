@@ -9,3 +9,17 @@ pub use position::*;
 
 pub mod stake;
 pub use stake::*;
+
+pub mod auction;
+pub use auction::*;
+
+pub mod oracle_config;
+pub use oracle_config::*;
+
+#[cfg(feature = "mock-oracle")]
+pub mod mock_price;
+#[cfg(feature = "mock-oracle")]
+pub use mock_price::*;
+
+pub mod pause;
+pub use pause::*;
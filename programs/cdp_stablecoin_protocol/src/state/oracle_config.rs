@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::BPS_SCALE, errors::OracleError};
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq)]
+pub enum OracleSource {
+    Pyth,
+    Switchboard,
+    // Admin written prices, only readable when built with the mock-oracle feature
+    Mock,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq)]
+pub enum PriceMode {
+    Spot,
+    Ema,
+    // Whichever of spot and EMA protects the protocol for the decision being made
+    Conservative,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy)]
+pub struct OracleConfig {
+    // Backend the price feed account is read from
+    pub source: OracleSource,
+    pub price_mode: PriceMode,
+    // Oldest publish time accepted, in seconds
+    pub max_price_age: u64,
+    // Widest confidence interval accepted, in bps of the price
+    pub max_confidence: u16,
+}
+
+impl OracleConfig {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.source != OracleSource::Mock || cfg!(feature = "mock-oracle"),
+            OracleError::MockOracleDisabled
+        );
+        require!(self.max_price_age > 0, OracleError::InvalidOracleConfig);
+        require!(
+            self.max_confidence > 0 && self.max_confidence <= BPS_SCALE,
+            OracleError::InvalidOracleConfig
+        );
+
+        Ok(())
+    }
+}
+
+// Second price source a collateral's primary oracle is checked against
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy)]
+pub struct SecondaryOracle {
+    pub price_feed: [u8; 32],
+    pub oracle: OracleConfig,
+    // Largest gap accepted between the two prices, in bps of the primary price
+    pub max_deviation: u16,
+}
+
+impl SecondaryOracle {
+    pub fn validate(&self) -> Result<()> {
+        require!(self.price_feed != [0; 32], OracleError::InvalidFeedId);
+        require!(
+            self.max_deviation > 0 && self.max_deviation <= BPS_SCALE,
+            OracleError::InvalidOracleConfig
+        );
+
+        self.oracle.validate()
+    }
+}
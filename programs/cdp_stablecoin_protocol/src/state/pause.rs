@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::PauseError;
+
+// One switch per user facing action, kept on the protocol config and on every collateral
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Default)]
+pub struct PauseFlags {
+    // Opening positions, borrowing more and withdrawing collateral
+    pub open: bool,
+    // Repaying, depositing collateral and closing positions
+    pub repay: bool,
+    // Stability pool liquidations and auctions
+    pub liquidate: bool,
+    pub redeem: bool,
+    pub stake: bool,
+    pub unstake: bool,
+    pub claim: bool,
+    // Interest rate updates and interest collection, only read at protocol level
+    pub interest_update: bool,
+}
+
+#[derive(Clone, Copy)]
+pub enum PauseAction {
+    Open,
+    Repay,
+    Liquidate,
+    Redeem,
+    Stake,
+    Unstake,
+    Claim,
+    InterestUpdate,
+}
+
+impl PauseFlags {
+    fn as_array(&self) -> [bool; 8] {
+        [
+            self.open,
+            self.repay,
+            self.liquidate,
+            self.redeem,
+            self.stake,
+            self.unstake,
+            self.claim,
+            self.interest_update,
+        ]
+    }
+
+    pub fn check(&self, action: PauseAction) -> Result<()> {
+        match action {
+            PauseAction::Open => require!(!self.open, PauseError::OpenPaused),
+            PauseAction::Repay => require!(!self.repay, PauseError::RepayPaused),
+            PauseAction::Liquidate => require!(!self.liquidate, PauseError::LiquidatePaused),
+            PauseAction::Redeem => require!(!self.redeem, PauseError::RedeemPaused),
+            PauseAction::Stake => require!(!self.stake, PauseError::StakePaused),
+            PauseAction::Unstake => require!(!self.unstake, PauseError::UnstakePaused),
+            PauseAction::Claim => require!(!self.claim, PauseError::ClaimPaused),
+            PauseAction::InterestUpdate => {
+                require!(!self.interest_update, PauseError::InterestUpdatePaused)
+            }
+        }
+
+        Ok(())
+    }
+
+    // The guardian can only switch flags on, clearing any of them needs the admin
+    pub fn update(&mut self, paused: PauseFlags, is_admin: bool) -> Result<()> {
+        let clears_flag = self
+            .as_array()
+            .iter()
+            .zip(paused.as_array().iter())
+            .any(|(current, new)| *current && !*new);
+
+        require!(!clears_flag || is_admin, PauseError::OnlyAdminCanUnpause);
+
+        *self = paused;
+
+        Ok(())
+    }
+}
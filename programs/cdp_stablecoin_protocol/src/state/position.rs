@@ -7,4 +7,9 @@ pub struct Position {
     pub collateral_amount: u64,
     pub debt_amount: u64,
     pub prev_cumulative_interest_rate: u128,
+    pub redistributed_collateral_snapshot: u128,
+    pub redistributed_debt_snapshot: u128,
+    // Riskier and safer neighbours in the collateral's position list, see PositionList
+    pub prev_position: Option<Pubkey>,
+    pub next_position: Option<Pubkey>,
 }
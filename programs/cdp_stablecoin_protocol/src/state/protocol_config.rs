@@ -1,9 +1,19 @@
-use crate::{constants::INTEREST_SCALE, errors::ArithmeticError, state::Position};
+use crate::{
+    constants::{BPS_SCALE, INTEREST_SCALE, MAX_INTEREST_RATE, MIN_INTEREST_RATE},
+    errors::{AdminError, ArithmeticError, PositionError},
+    state::{OracleConfig, PauseFlags, Position},
+};
 use anchor_lang::prelude::*;
 
 #[account]
 #[derive(InitSpace)]
 pub struct ProtocolConfig {
+    pub admin: Pubkey,
+    // Set by propose_admin, becomes admin once it signs accept_admin
+    pub pending_admin: Option<Pubkey>,
+    // Can set pause flags but not clear them
+    pub guardian: Pubkey,
+    pub paused: PauseFlags,
     pub stable_mint: Pubkey,
     pub protocol_fee: u16,
     pub redemption_fee: u16,
@@ -14,26 +24,76 @@ pub struct ProtocolConfig {
     pub bump: u8,
     pub cumulative_interest_rate: u128,
     pub last_interest_rate_update: i64,
-    #[max_len(64)]
-    pub stablecoin_price_feed: String,
+    // Yearly rate set by the last update_interest_rate, in INTEREST_SCALE
+    pub interest_rate: u128,
+    pub stablecoin_price_feed: [u8; 32],
+    pub stablecoin_oracle: OracleConfig,
     pub total_debt: u128,
+    // Most stablecoin debt that can be outstanding across every collateral
+    pub debt_ceiling: u64,
     pub total_stake_amount: u128,
     pub deposit_depletion_factor: u16,
+    pub accrued_interest: u128,
+    pub interest_gain_summation: u128,
+    // Bumped whenever a liquidation empties the stability pool, deposits made in earlier
+    // epochs are worth nothing and only keep their unclaimed gains
+    pub stake_epoch: u64,
+    // interest_gain_summation when the last epoch ended
+    pub prev_epoch_interest_gain_summation: u128,
 }
 
 impl ProtocolConfig {
     pub const INITIAL_CUMULATIVE_RATE: u128 = INTEREST_SCALE;
 
+    // Fees are fractions of an amount, and the base rate must sit inside the range
+    // update_interest_rate clamps to, sigma divides the peg deviation so it cannot be zero
+    pub fn validate_parameters(
+        protocol_fee: u16,
+        redemption_fee: u16,
+        mint_fee: u16,
+        base_rate: u16,
+        sigma: u16,
+    ) -> Result<()> {
+        require!(
+            protocol_fee <= BPS_SCALE && redemption_fee < BPS_SCALE && mint_fee < BPS_SCALE,
+            AdminError::InvalidProtocolParameters
+        );
+
+        let base_rate = (base_rate as u128) * INTEREST_SCALE / BPS_SCALE as u128;
+
+        require!(
+            (MIN_INTEREST_RATE..=MAX_INTEREST_RATE).contains(&base_rate),
+            AdminError::InvalidProtocolParameters
+        );
+
+        require!(
+            sigma > 0 && sigma <= BPS_SCALE,
+            AdminError::InvalidProtocolParameters
+        );
+
+        Ok(())
+    }
+
     pub fn calculate_current_debt(&self, position: &Position) -> Result<u64> {
         let current_debt = (position.debt_amount as u128)
-            .checked_mul(self.cumulative_interest_rate as u128)
+            .checked_mul(self.cumulative_interest_rate)
             .ok_or(ArithmeticError::ArithmeticOverflow)?
-            .checked_div(position.prev_cumulative_interest_rate as u128)
+            .checked_div(position.prev_cumulative_interest_rate)
             .ok_or(ArithmeticError::ArithmeticOverflow)? as u64;
 
         Ok(current_debt)
     }
 
+    pub fn calculate_mint_fee(&self, debt_amount: u64) -> Result<u64> {
+        let fee = (debt_amount as u128)
+            .checked_mul(self.mint_fee as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(BPS_SCALE as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)? as u64;
+
+        Ok(fee)
+    }
+
     pub fn update_totals(&mut self, _debt_change: i64) -> Result<()> {
         if _debt_change > 0 {
             self.total_debt = self
@@ -49,4 +109,45 @@ impl ProtocolConfig {
 
         Ok(())
     }
+
+    // Take debt absorbed by the stability pool out of its deposits. Emptying the pool, or
+    // depleting it so far that the factor rounds to zero, starts a new epoch with a fresh
+    // factor, whatever dust is left in that case can no longer be withdrawn
+    pub fn deplete_stability_pool(&mut self, pool_debt: u64) -> Result<()> {
+        let remaining_stake_amount = self
+            .total_stake_amount
+            .checked_sub(pool_debt as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?;
+
+        let deposit_depletion_factor = (self.deposit_depletion_factor as u128)
+            .checked_mul(remaining_stake_amount)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(self.total_stake_amount)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            as u16;
+
+        if deposit_depletion_factor == 0 {
+            self.stake_epoch = self
+                .stake_epoch
+                .checked_add(1)
+                .ok_or(ArithmeticError::ArithmeticOverflow)?;
+            self.prev_epoch_interest_gain_summation = self.interest_gain_summation;
+            self.deposit_depletion_factor = BPS_SCALE;
+            self.total_stake_amount = 0;
+        } else {
+            self.deposit_depletion_factor = deposit_depletion_factor;
+            self.total_stake_amount = remaining_stake_amount;
+        }
+
+        Ok(())
+    }
+
+    pub fn check_debt_ceiling(&self) -> Result<()> {
+        require!(
+            self.total_debt <= self.debt_ceiling as u128,
+            PositionError::GlobalDebtCeilingExceeded
+        );
+
+        Ok(())
+    }
 }
@@ -1,5 +1,11 @@
 use anchor_lang::prelude::*;
 
+use crate::{
+    constants::{BPS_SCALE, GAIN_SCALE, INTEREST_SCALE},
+    errors::ArithmeticError,
+    state::{CollateralConfig, ProtocolConfig},
+};
+
 #[account]
 #[derive(InitSpace)]
 pub struct StakeAccount {
@@ -7,6 +13,228 @@ pub struct StakeAccount {
     pub amount: u64,
     pub init_deposit_depletion_factor: u16,
     pub init_gain_summation: u128,
+    pub init_interest_gain_summation: u128,
     pub last_staked: i64,
+    // Stability pool epoch of the deposit, set again on every claim
+    pub epoch: u64,
     pub bump: u8,
 }
+
+impl StakeAccount {
+    // Liquidated collateral earned since the last claim
+    pub fn collateral_gain(&self, collateral_config: &CollateralConfig) -> Result<u64> {
+        // Gains stop when the deposit's epoch ends. Only the latest value before the
+        // collateral's current epoch is kept, anything older is forfeited
+        let gain_summation = if self.epoch >= collateral_config.stake_epoch {
+            collateral_config.gain_summation
+        } else if self.epoch >= collateral_config.prev_stake_epoch {
+            collateral_config.prev_epoch_gain_summation
+        } else {
+            return Ok(0);
+        };
+
+        Ok((self.amount as u128)
+            .checked_mul(
+                gain_summation
+                    .checked_sub(self.init_gain_summation)
+                    .ok_or(ArithmeticError::ArithmeticOverflow)?,
+            )
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_mul(BPS_SCALE as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(self.init_deposit_depletion_factor as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(GAIN_SCALE)
+            .ok_or(ArithmeticError::ArithmeticOverflow)? as u64)
+    }
+
+    // Stablecoin interest distributed by collect_interest since the last claim
+    pub fn interest_gain(&self, protocol_config: &ProtocolConfig) -> Result<u64> {
+        let interest_gain_summation = if self.epoch == protocol_config.stake_epoch {
+            protocol_config.interest_gain_summation
+        } else if self.epoch.saturating_add(1) == protocol_config.stake_epoch {
+            protocol_config.prev_epoch_interest_gain_summation
+        } else {
+            return Ok(0);
+        };
+
+        Ok((self.amount as u128)
+            .checked_mul(
+                interest_gain_summation
+                    .checked_sub(self.init_interest_gain_summation)
+                    .ok_or(ArithmeticError::ArithmeticOverflow)?,
+            )
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_mul(BPS_SCALE as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(self.init_deposit_depletion_factor as u128)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(INTEREST_SCALE)
+            .ok_or(ArithmeticError::ArithmeticOverflow)? as u64)
+    }
+
+    // Deposit left after the liquidations absorbed since the last claim
+    pub fn compounded_amount(&self, protocol_config: &ProtocolConfig) -> Result<u64> {
+        // The pool was emptied since, taking the whole deposit with it
+        if self.epoch < protocol_config.stake_epoch {
+            return Ok(0);
+        }
+
+        Ok(self
+            .amount
+            .checked_mul(protocol_config.deposit_depletion_factor as u64)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?
+            .checked_div(self.init_deposit_depletion_factor as u64)
+            .ok_or(ArithmeticError::ArithmeticOverflow)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zeroed<T: AnchorDeserialize + Space>() -> T {
+        T::deserialize(&mut &vec![0; T::INIT_SPACE][..]).unwrap()
+    }
+
+    fn protocol_config() -> ProtocolConfig {
+        let mut protocol_config: ProtocolConfig = zeroed();
+        protocol_config.deposit_depletion_factor = BPS_SCALE;
+        protocol_config.interest_gain_summation = INTEREST_SCALE;
+        protocol_config
+    }
+
+    fn stake(
+        amount: u64,
+        protocol_config: &mut ProtocolConfig,
+        collateral_config: &CollateralConfig,
+    ) -> StakeAccount {
+        protocol_config.total_stake_amount += amount as u128;
+
+        StakeAccount {
+            user: Pubkey::default(),
+            amount,
+            init_deposit_depletion_factor: protocol_config.deposit_depletion_factor,
+            init_gain_summation: collateral_config.gain_summation,
+            init_interest_gain_summation: protocol_config.interest_gain_summation,
+            last_staked: 0,
+            epoch: protocol_config.stake_epoch,
+            bump: 0,
+        }
+    }
+
+    // What liquidate_position does with the stability pool's share
+    fn absorb(
+        pool_debt: u64,
+        pool_collateral: u64,
+        protocol_config: &mut ProtocolConfig,
+        collateral_config: &mut CollateralConfig,
+    ) {
+        collateral_config
+            .reward_stability_pool(pool_collateral, protocol_config)
+            .unwrap();
+        protocol_config.deplete_stability_pool(pool_debt).unwrap();
+    }
+
+    #[test]
+    fn partial_absorption_shrinks_deposits() {
+        let mut protocol_config = protocol_config();
+        let mut collateral_config: CollateralConfig = zeroed();
+        let stake = stake(3_000_000, &mut protocol_config, &collateral_config);
+
+        absorb(
+            1_000_000,
+            600_000,
+            &mut protocol_config,
+            &mut collateral_config,
+        );
+
+        assert_eq!(protocol_config.stake_epoch, 0);
+        assert_eq!(protocol_config.total_stake_amount, 2_000_000);
+        assert_eq!(
+            stake.compounded_amount(&protocol_config).unwrap(),
+            1_999_800
+        );
+        assert_eq!(stake.collateral_gain(&collateral_config).unwrap(), 600_000);
+    }
+
+    #[test]
+    fn emptying_the_pool_keeps_gains_and_starts_a_new_epoch() {
+        let mut protocol_config = protocol_config();
+        let mut collateral_config: CollateralConfig = zeroed();
+        let old_stake = stake(3_000_000, &mut protocol_config, &collateral_config);
+
+        protocol_config.interest_gain_summation += INTEREST_SCALE;
+        absorb(
+            3_000_000,
+            900_000,
+            &mut protocol_config,
+            &mut collateral_config,
+        );
+
+        assert_eq!(protocol_config.stake_epoch, 1);
+        assert_eq!(protocol_config.deposit_depletion_factor, BPS_SCALE);
+        assert_eq!(protocol_config.total_stake_amount, 0);
+
+        // The emptied deposit is gone but its gains can still be claimed
+        assert_eq!(old_stake.compounded_amount(&protocol_config).unwrap(), 0);
+        assert_eq!(
+            old_stake.collateral_gain(&collateral_config).unwrap(),
+            900_000
+        );
+        assert_eq!(
+            old_stake.interest_gain(&protocol_config).unwrap(),
+            3_000_000
+        );
+
+        // A new deposit earns from later liquidations, the old one does not
+        let new_stake = stake(2_000_000, &mut protocol_config, &collateral_config);
+        protocol_config.interest_gain_summation += INTEREST_SCALE;
+        absorb(
+            1_000_000,
+            400_000,
+            &mut protocol_config,
+            &mut collateral_config,
+        );
+
+        assert_eq!(
+            new_stake.compounded_amount(&protocol_config).unwrap(),
+            1_000_000
+        );
+        assert_eq!(
+            new_stake.collateral_gain(&collateral_config).unwrap(),
+            400_000
+        );
+        assert_eq!(
+            new_stake.interest_gain(&protocol_config).unwrap(),
+            2_000_000
+        );
+        assert_eq!(
+            old_stake.collateral_gain(&collateral_config).unwrap(),
+            900_000
+        );
+        assert_eq!(
+            old_stake.interest_gain(&protocol_config).unwrap(),
+            3_000_000
+        );
+    }
+
+    #[test]
+    fn depleting_the_factor_to_zero_starts_a_new_epoch() {
+        let mut protocol_config = protocol_config();
+        let mut collateral_config: CollateralConfig = zeroed();
+        stake(3_000_000, &mut protocol_config, &collateral_config);
+
+        // 100 units left is below a single bps of the pool
+        absorb(
+            2_999_900,
+            900_000,
+            &mut protocol_config,
+            &mut collateral_config,
+        );
+
+        assert_eq!(protocol_config.stake_epoch, 1);
+        assert_eq!(protocol_config.deposit_depletion_factor, BPS_SCALE);
+        assert_eq!(protocol_config.total_stake_amount, 0);
+    }
+}